@@ -5,7 +5,10 @@ rustup target add x86_64-unknown-linux-gnu
 rustup target add thumbv7em-none-eabihf
 
 cargo check --all-targets --target x86_64-unknown-linux-gnu
-cargo check --target thumbv7em-none-eabihf --no-default-features # no_std, not for all targets because example needs std
+cargo check --all-targets --target x86_64-unknown-linux-gnu --all-features
+cargo check --target thumbv7em-none-eabihf --no-default-features # no_std, not for all targets because tests need std
+cargo check --target thumbv7em-none-eabihf --no-default-features --features alloc
+cargo check --target thumbv7em-none-eabihf --no-default-features --features async
 
 # doesn't work on Windows
 cargo test --all-targets --target x86_64-unknown-linux-gnu
@@ -0,0 +1,63 @@
+//! Measures the frame rate that is achieved with the bit-banged GPIO pins for different minimum
+//! clock half-periods. Use it to find the fastest setting at which the displays still show the
+//! pattern correctly, then pass it to `DisplayConfig::clock_half_period_us()`.
+
+use max_7219_led_matrix_util::animation::Frame;
+use max_7219_led_matrix_util::{DisplayConfig, Error};
+use std::time::Instant;
+
+const NUM_DISPLAYS: usize = 4;
+const FRAMES: usize = 200;
+
+fn main() -> Result<(), Error> {
+    println!(
+        "Provide 3 pins (gpio pin nums) please and connect all to the device: <data> <cs> <clk>"
+    );
+    println!(
+        "and optionally the clock half-periods in microseconds to measure, e.g. '12 16 21 0 1 2 5'"
+    );
+    println!();
+
+    let args: Vec<String> = std::env::args().collect();
+    assert!(args.len() >= 4, "Provide three args!");
+
+    let data_pin = args[1].parse::<u32>().unwrap();
+    let cs_pin = args[2].parse::<u32>().unwrap();
+    let clk_pin = args[3].parse::<u32>().unwrap();
+    let mut half_periods = args[4..]
+        .iter()
+        .map(|arg| arg.parse::<u32>().unwrap())
+        .collect::<Vec<_>>();
+    if half_periods.is_empty() {
+        half_periods = vec![0, 1, 2, 5, 10];
+    }
+
+    // a checkerboard and its inverse, so that all rows change with each frame
+    let mut frames = [Frame::new(NUM_DISPLAYS), Frame::new(NUM_DISPLAYS)];
+    let checkerboard = [0xAA, 0x55, 0xAA, 0x55, 0xAA, 0x55, 0xAA, 0x55];
+    frames[0].set_data(&[checkerboard; NUM_DISPLAYS]);
+    frames[1].set_data(&[checkerboard.map(|row| !row); NUM_DISPLAYS]);
+
+    for half_period_us in half_periods {
+        let mut display = DisplayConfig::new(NUM_DISPLAYS)
+            .data_pin(data_pin)
+            .cs_pin(cs_pin)
+            .clk_pin(clk_pin)
+            .clock_half_period_us(half_period_us)
+            .build()?
+            .shutdown_on_drop();
+
+        let start = Instant::now();
+        for i in 0..FRAMES {
+            display.write_frame(&frames[i % 2])?;
+        }
+        let elapsed = start.elapsed();
+        println!(
+            "half-period {:>3} µs: {:>7.1} frames/s ({:?} per frame)",
+            half_period_us,
+            FRAMES as f64 / elapsed.as_secs_f64(),
+            elapsed / FRAMES as u32
+        );
+    }
+    Ok(())
+}
@@ -1,21 +1,28 @@
 //! Demo for the 4-display device by AzDelivery. This is the device in the gif in the README.md.
 //! (https://www.az-delivery.de/products/4-x-64er-led-matrix-display).
 
-use max_7219_led_matrix_util::setup_adapter;
-use max_7219_led_matrix_util::{prepare_display, show_moving_text_in_loop};
+use max_7219_led_matrix_util::terminal::{TerminalDevice, TerminalStyle};
+use max_7219_led_matrix_util::{Display, DisplayConfig, Error};
 
 const NUM_DISPLAYS: usize = 4;
 
-fn main() {
+fn main() -> Result<(), Error> {
     println!("Demo for the 4-display device by AzDelivery. This is the device in the gif in the README.md.");
     println!();
     println!(
         "Provide 3 pins (gpio pin nums) please and connect all to the device: <data> <cs> <clk>"
     );
     println!("for example: '12 16 21'");
+    println!("Or run it without hardware in the terminal: '--terminal'");
     println!();
 
     let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("--terminal") {
+        let device = TerminalDevice::new(NUM_DISPLAYS, TerminalStyle::Ansi);
+        let mut display = Display::new(device, NUM_DISPLAYS)?;
+        display.prepare(0x0F)?;
+        return display.show_moving_text_in_loop("HALLO LEON!!!  ", 50, None);
+    }
     assert_eq!(args.len(), 4, "Provide three args!");
 
     let data_pin = args[1].parse::<u32>().unwrap();
@@ -25,15 +32,17 @@ fn main() {
     println!("data={}, cs={}, clk={}", data_pin, cs_pin, clk_pin);
 
     // display adapter (std-feature, doesn't work in no_std)
-    let mut display = setup_adapter("/dev/gpiochip0", NUM_DISPLAYS, data_pin, cs_pin, clk_pin);
-    prepare_display(&mut display, NUM_DISPLAYS, 0x0F);
-    show_moving_text_in_loop(
-        &mut display,
+    let mut display = DisplayConfig::new(NUM_DISPLAYS)
+        .data_pin(data_pin)
+        .cs_pin(cs_pin)
+        .clk_pin(clk_pin)
+        .intensity(0x0F)
+        .build()?;
+    display.show_moving_text_in_loop(
         "HALLO LEON!!!  ",
-        NUM_DISPLAYS,
         // ms for each animation step
         50,
         // gap_width
         None,
-    );
+    )
 }
@@ -0,0 +1,413 @@
+//! Frame based animations for a chain of displays. An [`Animation`] renders one step at a time
+//! into a [`Frame`]. Animations can be chained with [`Animation::then`], so that for example a
+//! [`Scroller`] can be followed by a [`crate::transition::Transition`] to the next message.
+
+use crate::encoding::{encode_string, TextColumns};
+use crate::mappings::SingleDisplayData;
+use crate::{
+    remove_gaps_in_display_text, shift_all_rows_one_bit_left, shift_in_col, LED_SQUARE_MATRIX_DIM,
+};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// The content of the whole display chain after one step of an [`Animation`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    /// Pixel data of each display. Index 0 is the leftmost display.
+    pub data: Vec<SingleDisplayData>,
+    /// Brightness of each display (`0x00` to `0x0F`). `None` keeps the current brightness.
+    pub intensity: Vec<Option<u8>>,
+    /// Whether the displays are powered on. `None` keeps the current state. Powering the
+    /// displays off keeps their content, so this is a cheap way to hide the content.
+    pub power: Option<bool>,
+}
+
+impl Frame {
+    /// Creates an empty (all pixels off) frame for `display_count` displays.
+    pub fn new(display_count: usize) -> Self {
+        Self {
+            data: vec![[0; LED_SQUARE_MATRIX_DIM]; display_count],
+            intensity: vec![None; display_count],
+            power: None,
+        }
+    }
+
+    /// Shows `content` on the frame. Displays without content are cleared; content that doesn't
+    /// fit on the frame is ignored.
+    pub fn set_data(&mut self, content: &[SingleDisplayData]) {
+        for (i, display_data) in self.data.iter_mut().enumerate() {
+            *display_data = content.get(i).copied().unwrap_or_default();
+        }
+    }
+
+    /// Sets the brightness of all displays.
+    pub fn set_intensity(&mut self, intensity: u8) {
+        self.intensity.iter_mut().for_each(|i| *i = Some(intensity));
+    }
+
+    /// Width of the frame in pixels (columns).
+    pub fn width(&self) -> usize {
+        self.data.len() * LED_SQUARE_MATRIX_DIM
+    }
+}
+
+/// An animation that is rendered frame by frame.
+pub trait Animation {
+    /// Renders the next step of the animation into `frame`. Returns `false` if the animation is
+    /// finished. In that case `frame` is left untouched.
+    fn next_frame(&mut self, frame: &mut Frame) -> bool;
+
+    /// Plays `next` after this animation is finished.
+    fn then<B: Animation>(self, next: B) -> Then<Self, B>
+    where
+        Self: Sized,
+    {
+        Then {
+            first: self,
+            second: next,
+            first_done: false,
+        }
+    }
+
+    /// Stops this animation as soon as `stop` is set, e.g. by a signal handler. Useful to end
+    /// endless animations, like a [`Scroller`], cleanly.
+    fn until(self, stop: &AtomicBool) -> Until<'_, Self>
+    where
+        Self: Sized,
+    {
+        Until {
+            animation: self,
+            stop,
+        }
+    }
+}
+
+impl<A: Animation + ?Sized> Animation for &mut A {
+    fn next_frame(&mut self, frame: &mut Frame) -> bool {
+        (**self).next_frame(frame)
+    }
+}
+
+impl<A: Animation + ?Sized> Animation for Box<A> {
+    fn next_frame(&mut self, frame: &mut Frame) -> bool {
+        (**self).next_frame(frame)
+    }
+}
+
+/// Two animations played after each other. See [`Animation::then`].
+#[derive(Debug)]
+pub struct Then<A, B> {
+    first: A,
+    second: B,
+    first_done: bool,
+}
+
+impl<A: Animation, B: Animation> Animation for Then<A, B> {
+    fn next_frame(&mut self, frame: &mut Frame) -> bool {
+        if !self.first_done {
+            if self.first.next_frame(frame) {
+                return true;
+            }
+            self.first_done = true;
+        }
+        self.second.next_frame(frame)
+    }
+}
+
+/// An animation that stops as soon as a flag is set. See [`Animation::until`].
+#[derive(Debug)]
+pub struct Until<'a, A> {
+    animation: A,
+    stop: &'a AtomicBool,
+}
+
+impl<A: Animation> Animation for Until<'_, A> {
+    fn next_frame(&mut self, frame: &mut Frame) -> bool {
+        !self.stop.load(Ordering::Relaxed) && self.animation.next_frame(frame)
+    }
+}
+
+/// Moves a text from the right to the left over the display chain. After each frame all bits
+/// are shifted one col to the left.
+#[derive(Debug, Clone)]
+pub struct Scroller {
+    data: Vec<SingleDisplayData>,
+    /// Shifts done in the current pass.
+    shifts: usize,
+    /// Remaining passes through the whole text; `None` for endless scrolling.
+    passes: Option<usize>,
+}
+
+impl Scroller {
+    /// Creates an endless scroller for `text`.
+    ///
+    /// * `text` - the text to display
+    /// * `gap_width` - (optional) set's the maximum width/count of empty cols between characters.
+    ///                 Downside is that spaces will be removed.
+    pub fn new(text: &str, gap_width: Option<usize>) -> Self {
+        Self::from_data(layout_text(text, gap_width))
+    }
+
+    /// Creates an endless scroller for already encoded display data.
+    pub const fn from_data(data: Vec<SingleDisplayData>) -> Self {
+        Self {
+            data,
+            shifts: 0,
+            passes: None,
+        }
+    }
+
+    /// Stops the scroller after the text moved `passes` times through the display chain.
+    pub const fn passes(mut self, passes: usize) -> Self {
+        self.passes = Some(passes);
+        self
+    }
+
+    /// Returns the content that the next call to [`Animation::next_frame`] shows on a chain of
+    /// `display_count` displays. Useful as target of a [`crate::transition::Transition`].
+    pub fn current_frame(&self, display_count: usize) -> Vec<SingleDisplayData> {
+        let mut frame = Frame::new(display_count);
+        frame.set_data(&self.data);
+        frame.data
+    }
+}
+
+impl Animation for Scroller {
+    fn next_frame(&mut self, frame: &mut Frame) -> bool {
+        let pass_len = self.data.len() * LED_SQUARE_MATRIX_DIM;
+        if pass_len == 0 || self.passes == Some(0) {
+            return false;
+        }
+
+        frame.set_data(&self.data);
+        shift_all_rows_one_bit_left(&mut self.data);
+
+        self.shifts += 1;
+        if self.shifts == pass_len {
+            self.shifts = 0;
+            if let Some(passes) = self.passes.as_mut() {
+                *passes -= 1;
+            }
+        }
+        true
+    }
+}
+
+/// Like [`Scroller`] but encodes the text while it moves onto the display chain, see
+/// [`TextColumns`]. Only the content of the display chain is kept in memory, so the memory use
+/// doesn't grow with the length of the text, e.g. for a long news ticker. After the end of the
+/// text, the text starts again; a text that is shorter than the display chain is repeated.
+#[derive(Debug, Clone)]
+pub struct TextScroller<I> {
+    /// The cols of the text from its begin; used to start the next pass.
+    text: TextColumns<I>,
+    cols: TextColumns<I>,
+    /// The content that the next frame shows.
+    window: Vec<SingleDisplayData>,
+    /// Count of cols of the whole text.
+    pass_len: usize,
+    /// Shifts done in the current pass.
+    shifts: usize,
+    /// Remaining passes through the whole text; `None` for endless scrolling.
+    passes: Option<usize>,
+}
+
+impl<I: Iterator<Item = char> + Clone> TextScroller<I> {
+    /// Creates an endless scroller for the given characters.
+    ///
+    /// * `chars` - the characters of the text, e.g. `"HELLO".chars()`
+    /// * `gap_width` - (optional) set's the maximum width/count of empty cols between characters.
+    ///                 Downside is that spaces will be removed.
+    pub fn new(chars: I, gap_width: Option<usize>) -> Self {
+        let text = TextColumns::new(chars, gap_width);
+        Self {
+            cols: text.clone(),
+            pass_len: text.clone().count(),
+            text,
+            window: Vec::new(),
+            shifts: 0,
+            passes: None,
+        }
+    }
+
+    /// Stops the scroller after the text moved `passes` times through the display chain.
+    pub fn passes(mut self, passes: usize) -> Self {
+        self.passes = Some(passes);
+        self
+    }
+
+    /// The next col of the text; starts again after the end of the text.
+    fn next_col(&mut self) -> u8 {
+        self.cols.next().unwrap_or_else(|| {
+            self.cols = self.text.clone();
+            self.cols.next().unwrap_or_default()
+        })
+    }
+}
+
+impl<I: Iterator<Item = char> + Clone> Animation for TextScroller<I> {
+    fn next_frame(&mut self, frame: &mut Frame) -> bool {
+        if self.pass_len == 0 || self.passes == Some(0) {
+            return false;
+        }
+
+        if self.window.len() != frame.data.len() {
+            self.window = vec![[0; LED_SQUARE_MATRIX_DIM]; frame.data.len()];
+            for _ in 0..frame.width() {
+                let col = self.next_col();
+                shift_in_col(&mut self.window, col);
+            }
+        }
+        frame.set_data(&self.window);
+        let col = self.next_col();
+        shift_in_col(&mut self.window, col);
+
+        self.shifts += 1;
+        if self.shifts == self.pass_len {
+            self.shifts = 0;
+            if let Some(passes) = self.passes.as_mut() {
+                *passes -= 1;
+            }
+        }
+        true
+    }
+}
+
+/// Shows a content for a fixed count of frames.
+#[derive(Debug, Clone)]
+pub struct Hold {
+    frames: usize,
+    content: Option<Vec<SingleDisplayData>>,
+}
+
+impl Hold {
+    /// Keeps the content of the previous frame on the display for `frames` frames.
+    pub const fn new(frames: usize) -> Self {
+        Self {
+            frames,
+            content: None,
+        }
+    }
+
+    /// Sets the content to show.
+    pub fn content(mut self, content: Vec<SingleDisplayData>) -> Self {
+        self.content = Some(content);
+        self
+    }
+}
+
+impl Animation for Hold {
+    fn next_frame(&mut self, frame: &mut Frame) -> bool {
+        if self.frames == 0 {
+            return false;
+        }
+        self.frames -= 1;
+        if let Some(content) = &self.content {
+            frame.set_data(content);
+        }
+        true
+    }
+}
+
+/// Encodes `text` and removes the gaps between the characters, if `gap_width` is set.
+pub(crate) fn layout_text(text: &str, gap_width: Option<usize>) -> Vec<SingleDisplayData> {
+    let raw_bits = encode_string(text);
+    if let Some(gap_width) = gap_width {
+        remove_gaps_in_display_text(&raw_bits, gap_width)
+    } else {
+        raw_bits
+    }
+}
+
+/// Returns whether the pixel at col `x` and row `y` of the display chain is on.
+/// Col 0 is the leftmost col of the first display. Pixels outside of `data` are off.
+pub(crate) fn pixel(data: &[SingleDisplayData], x: usize, y: usize) -> bool {
+    data.get(x / LED_SQUARE_MATRIX_DIM)
+        .map(|display| display[y] & (0b1000_0000 >> (x % LED_SQUARE_MATRIX_DIM)) != 0)
+        .unwrap_or(false)
+}
+
+/// Turns the pixel at col `x` and row `y` of the display chain on or off.
+pub(crate) fn set_pixel(data: &mut [SingleDisplayData], x: usize, y: usize, on: bool) {
+    let mask = 0b1000_0000 >> (x % LED_SQUARE_MATRIX_DIM);
+    let row = &mut data[x / LED_SQUARE_MATRIX_DIM][y];
+    if on {
+        *row |= mask;
+    } else {
+        *row &= !mask;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scroller_stops_after_passes() {
+        let mut scroller = Scroller::from_data(vec![[0b1000_0000; 8]]).passes(2);
+        let mut frame = Frame::new(1);
+        let mut count = 0;
+        while scroller.next_frame(&mut frame) {
+            count += 1;
+        }
+        assert_eq!(count, 16);
+    }
+
+    #[test]
+    fn test_text_scroller_like_scroller() {
+        let mut scroller = Scroller::new("HELLO", None);
+        let mut text_scroller = TextScroller::new("HELLO".chars(), None).passes(1);
+        let mut expected = Frame::new(2);
+        let mut frame = Frame::new(2);
+        // the same until the end of the text reaches the display chain
+        for _ in 0..3 * 8 {
+            assert!(scroller.next_frame(&mut expected));
+            assert!(text_scroller.next_frame(&mut frame));
+            assert_eq!(frame, expected);
+        }
+        let mut count = 3 * 8;
+        while text_scroller.next_frame(&mut frame) {
+            count += 1;
+        }
+        assert_eq!(count, 5 * 8);
+        assert!(!TextScroller::new("".chars(), None).next_frame(&mut frame));
+    }
+
+    #[test]
+    fn test_then_plays_both_animations() {
+        let first = Scroller::from_data(vec![[0b1000_0000; 8]]).passes(1);
+        let second = Scroller::from_data(vec![[0b0000_0001; 8]]).passes(1);
+        let mut animation = first.then(second);
+        let mut frame = Frame::new(1);
+        let mut frames = Vec::new();
+        while animation.next_frame(&mut frame) {
+            frames.push(frame.data[0][0]);
+        }
+        assert_eq!(frames.len(), 16);
+        assert_eq!(frames[0], 0b1000_0000);
+        assert_eq!(frames[8], 0b0000_0001);
+    }
+
+    #[test]
+    fn test_until_stops_when_flag_is_set() {
+        let stop = AtomicBool::new(false);
+        let mut animation = Scroller::from_data(vec![[0b1000_0000; 8]]).until(&stop);
+        let mut frame = Frame::new(1);
+        assert!(animation.next_frame(&mut frame));
+        stop.store(true, Ordering::Relaxed);
+        assert!(!animation.next_frame(&mut frame));
+    }
+
+    #[test]
+    fn test_pixel() {
+        let mut data = vec![[0; 8]; 2];
+        set_pixel(&mut data, 9, 3, true);
+        assert_eq!(data[1][3], 0b0100_0000);
+        assert!(pixel(&data, 9, 3));
+        assert!(!pixel(&data, 8, 3));
+        assert!(!pixel(&data, 100, 3));
+        set_pixel(&mut data, 9, 3, false);
+        assert_eq!(data[1][3], 0);
+    }
+}
@@ -0,0 +1,481 @@
+//! Async versions of the display functions for executors like embassy (on microcontrollers) or
+//! tokio (e.g. on the Raspberry Pi). They await an async delay instead of blocking, so the
+//! display can share the executor with other tasks.
+//!
+//! [`AsyncMax7219`] drives the chain via an async SPI device from "embedded-hal-async". Every
+//! synchronous [`MatrixDevice`], like the adapters of `setup_adapter()`, can be used via
+//! [`Blocking`].
+//!
+//! This module needs Rust 1.75 or newer.
+
+#[cfg(feature = "alloc")]
+use crate::animation::{Animation, Frame, Scroller};
+use crate::device::MatrixDevice;
+use crate::driver::{check_scan_limit, Register, Transfer};
+use crate::mappings::SingleDisplayData;
+use crate::{
+    check_display_count, check_display_count_of, Error, LED_SQUARE_MATRIX_DIM, MAX_DISPLAYS,
+};
+#[cfg(feature = "alloc")]
+use crate::{frame_changes, FrameChange};
+#[cfg(feature = "alloc")]
+use embedded_hal_async::delay::DelayNs;
+use embedded_hal_async::spi::SpiDevice;
+use max7219::{DataError, DecodeMode};
+
+/// Async version of [`MatrixDevice`]. Module 0 is the first display of the chain.
+#[allow(async_fn_in_trait)]
+pub trait AsyncMatrixDevice {
+    /// Writes the 8 rows of the module with the given index.
+    async fn write_rows(&mut self, module: usize, rows: &SingleDisplayData) -> Result<(), Error>;
+
+    /// Sets the brightness of the module with the given index; value between `0x00` and `0x0F`.
+    async fn set_intensity(&mut self, module: usize, intensity: u8) -> Result<(), Error>;
+
+    /// Powers on all modules.
+    async fn power_on(&mut self) -> Result<(), Error>;
+
+    /// Powers off all modules. The modules keep their content.
+    async fn power_off(&mut self) -> Result<(), Error>;
+
+    /// Sets the scan limit of the module with the given index, i.e. the index of the last row
+    /// (digit) that is displayed; value between `0` and `7`.
+    async fn set_scan_limit(&mut self, module: usize, limit: u8) -> Result<(), Error>;
+
+    /// Sets the decode mode of the module with the given index.
+    async fn set_decode_mode(&mut self, module: usize, mode: DecodeMode) -> Result<(), Error>;
+
+    /// Turns the display test mode of the module with the given index on or off.
+    async fn set_display_test(&mut self, module: usize, on: bool) -> Result<(), Error>;
+
+    /// Turns all pixels of the module with the given index off.
+    async fn clear(&mut self, module: usize) -> Result<(), Error> {
+        self.write_rows(module, &[0; LED_SQUARE_MATRIX_DIM]).await
+    }
+
+    /// Called after a whole frame was written. See [`MatrixDevice::flush`].
+    async fn flush(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Count of the modules of the chain, if the device knows it. See
+    /// [`MatrixDevice::module_count`].
+    fn module_count(&self) -> Option<usize> {
+        None
+    }
+}
+
+impl<D: AsyncMatrixDevice + ?Sized> AsyncMatrixDevice for &mut D {
+    async fn write_rows(&mut self, module: usize, rows: &SingleDisplayData) -> Result<(), Error> {
+        (**self).write_rows(module, rows).await
+    }
+
+    async fn set_intensity(&mut self, module: usize, intensity: u8) -> Result<(), Error> {
+        (**self).set_intensity(module, intensity).await
+    }
+
+    async fn power_on(&mut self) -> Result<(), Error> {
+        (**self).power_on().await
+    }
+
+    async fn power_off(&mut self) -> Result<(), Error> {
+        (**self).power_off().await
+    }
+
+    async fn set_scan_limit(&mut self, module: usize, limit: u8) -> Result<(), Error> {
+        (**self).set_scan_limit(module, limit).await
+    }
+
+    async fn set_decode_mode(&mut self, module: usize, mode: DecodeMode) -> Result<(), Error> {
+        (**self).set_decode_mode(module, mode).await
+    }
+
+    async fn set_display_test(&mut self, module: usize, on: bool) -> Result<(), Error> {
+        (**self).set_display_test(module, on).await
+    }
+
+    async fn clear(&mut self, module: usize) -> Result<(), Error> {
+        (**self).clear(module).await
+    }
+
+    async fn flush(&mut self) -> Result<(), Error> {
+        (**self).flush().await
+    }
+
+    fn module_count(&self) -> Option<usize> {
+        (**self).module_count()
+    }
+}
+
+/// Uses a synchronous [`MatrixDevice`] as [`AsyncMatrixDevice`]. The writes still block, but
+/// the waiting between the frames doesn't. Useful with fast devices, like the hardware SPI
+/// adapter on Linux, or for simulations.
+#[derive(Debug)]
+pub struct Blocking<D>(pub D);
+
+impl<D: MatrixDevice> AsyncMatrixDevice for Blocking<D> {
+    async fn write_rows(&mut self, module: usize, rows: &SingleDisplayData) -> Result<(), Error> {
+        self.0.write_rows(module, rows)
+    }
+
+    async fn set_intensity(&mut self, module: usize, intensity: u8) -> Result<(), Error> {
+        self.0.set_intensity(module, intensity)
+    }
+
+    async fn power_on(&mut self) -> Result<(), Error> {
+        self.0.power_on()
+    }
+
+    async fn power_off(&mut self) -> Result<(), Error> {
+        self.0.power_off()
+    }
+
+    async fn set_scan_limit(&mut self, module: usize, limit: u8) -> Result<(), Error> {
+        self.0.set_scan_limit(module, limit)
+    }
+
+    async fn set_decode_mode(&mut self, module: usize, mode: DecodeMode) -> Result<(), Error> {
+        self.0.set_decode_mode(module, mode)
+    }
+
+    async fn set_display_test(&mut self, module: usize, on: bool) -> Result<(), Error> {
+        self.0.set_display_test(module, on)
+    }
+
+    async fn clear(&mut self, module: usize) -> Result<(), Error> {
+        self.0.clear(module)
+    }
+
+    async fn flush(&mut self) -> Result<(), Error> {
+        self.0.flush()
+    }
+
+    fn module_count(&self) -> Option<usize> {
+        self.0.module_count()
+    }
+}
+
+/// Driver for a chain of MAX7219 that uses an async SPI device. The SPI device must use mode 0
+/// with at most 10 MHz and control the CS (LOAD) line.
+#[derive(Debug)]
+pub struct AsyncMax7219<SPI> {
+    spi: SPI,
+    display_count: usize,
+    /// The scan limit of each module. Rows after it are not sent.
+    scan_limits: [u8; MAX_DISPLAYS],
+}
+
+impl<SPI: SpiDevice> AsyncMax7219<SPI> {
+    /// Creates the driver for `display_count` chained displays. Call [`AsyncMax7219::init`]
+    /// before using it.
+    pub fn new(spi: SPI, display_count: usize) -> Result<Self, Error> {
+        check_display_count(display_count)?;
+        Ok(Self {
+            spi,
+            display_count,
+            scan_limits: [7; MAX_DISPLAYS],
+        })
+    }
+
+    /// Brings all modules into the same state as the initialization of the "max7219"-crate:
+    /// test mode off, all rows enabled, no decode mode, cleared and powered off.
+    pub async fn init(&mut self) -> Result<(), Error> {
+        self.write_all(Register::DisplayTest, 0).await?;
+        self.write_all(Register::ScanLimit, 7).await?;
+        self.scan_limits = [7; MAX_DISPLAYS];
+        self.write_all(Register::DecodeMode, DecodeMode::NoDecode as u8)
+            .await?;
+        for digit in 0..LED_SQUARE_MATRIX_DIM as u8 {
+            self.write_all(Register::Digit(digit), 0).await?;
+        }
+        self.write_all(Register::Shutdown, 0).await
+    }
+
+    /// Returns the SPI device.
+    pub fn into_inner(self) -> SPI {
+        self.spi
+    }
+
+    /// Writes the register of a single module. The other modules get a no-op.
+    async fn write(&mut self, module: usize, register: Register, value: u8) -> Result<(), Error> {
+        let transfer = Transfer::single(self.display_count, module, register, value)?;
+        self.send(transfer).await
+    }
+
+    /// Writes the register of all modules in a single transfer.
+    async fn write_all(&mut self, register: Register, value: u8) -> Result<(), Error> {
+        self.send(Transfer::all(self.display_count, register, value))
+            .await
+    }
+
+    async fn send(&mut self, transfer: Transfer) -> Result<(), Error> {
+        self.spi
+            .write(transfer.bytes())
+            .await
+            .map_err(|_| Error::Data(DataError::Spi))
+    }
+}
+
+impl<SPI: SpiDevice> AsyncMatrixDevice for AsyncMax7219<SPI> {
+    /// Only writes the rows up to the scan limit of the module; the other rows are not shown.
+    async fn write_rows(&mut self, module: usize, rows: &SingleDisplayData) -> Result<(), Error> {
+        if module >= self.display_count {
+            return Err(Error::InvalidModule(module));
+        }
+        let shown = usize::from(self.scan_limits[module]) + 1;
+        for (digit, row) in rows.iter().enumerate().take(shown) {
+            self.write(module, Register::Digit(digit as u8), *row)
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn set_intensity(&mut self, module: usize, intensity: u8) -> Result<(), Error> {
+        self.write(module, Register::Intensity, intensity).await
+    }
+
+    async fn power_on(&mut self) -> Result<(), Error> {
+        self.write_all(Register::Shutdown, 1).await
+    }
+
+    async fn power_off(&mut self) -> Result<(), Error> {
+        self.write_all(Register::Shutdown, 0).await
+    }
+
+    async fn set_scan_limit(&mut self, module: usize, limit: u8) -> Result<(), Error> {
+        check_scan_limit(limit)?;
+        self.write(module, Register::ScanLimit, limit).await?;
+        self.scan_limits[module] = limit;
+        Ok(())
+    }
+
+    async fn set_decode_mode(&mut self, module: usize, mode: DecodeMode) -> Result<(), Error> {
+        self.write(module, Register::DecodeMode, mode as u8).await
+    }
+
+    async fn set_display_test(&mut self, module: usize, on: bool) -> Result<(), Error> {
+        self.write(module, Register::DisplayTest, u8::from(on))
+            .await
+    }
+
+    fn module_count(&self) -> Option<usize> {
+        Some(self.display_count)
+    }
+}
+
+/// Async version of [`crate::prepare_display`].
+///
+/// * `display` - mutable reference to the display device
+/// * `display_count` - count of displays connected to the MAX7219
+/// * `intensity` - brightness for the display; value between `0x00` and `0x0F`
+pub async fn prepare_display<D: AsyncMatrixDevice>(
+    display: &mut D,
+    display_count: usize,
+    intensity: u8,
+) -> Result<(), Error> {
+    check_display_count_of(display.module_count(), display_count)?;
+
+    display.power_on().await?;
+    for i in 0..display_count {
+        display.set_decode_mode(i, DecodeMode::NoDecode).await?;
+        display.clear(i).await?;
+        display.set_intensity(i, intensity).await?;
+    }
+    display.flush().await
+}
+
+/// Async version of `show_moving_text_in_loop()`. This only returns if writing to the display
+/// fails.
+///
+/// * `display` - mutable reference to the display device
+/// * `text` - the text to display
+/// * `display_count` - count of displays connected to the MAX7219
+/// * `delay` - async delay provider, e.g. from embassy or a wrapper around tokio's sleep
+/// * `ms_sleep` - timeout after each iteration
+/// * `gap_width` - (optional) set's the maximum width/count of empty cols between characters.
+#[cfg(feature = "alloc")]
+pub async fn show_moving_text_in_loop<D: AsyncMatrixDevice, T: DelayNs>(
+    display: &mut D,
+    text: &str,
+    display_count: usize,
+    delay: &mut T,
+    ms_sleep: u32,
+    gap_width: Option<usize>,
+) -> Result<(), Error> {
+    play_animation(
+        display,
+        Scroller::new(text, gap_width),
+        display_count,
+        delay,
+        ms_sleep,
+    )
+    .await
+}
+
+/// Async version of [`crate::write_frame`].
+///
+/// * `display` - mutable reference to the display device
+/// * `frame` - the frame to display
+/// * `display_count` - count of displays connected to the MAX7219
+#[cfg(feature = "alloc")]
+pub async fn write_frame<D: AsyncMatrixDevice>(
+    display: &mut D,
+    frame: &Frame,
+    display_count: usize,
+) -> Result<(), Error> {
+    check_display_count_of(display.module_count(), display_count)?;
+    write_frame_changes(display, frame, None, display_count).await
+}
+
+/// Async version of `play_animation()`. Awaits `delay` after each frame.
+///
+/// * `display` - mutable reference to the display device
+/// * `animation` - the animation to play
+/// * `display_count` - count of displays connected to the MAX7219
+/// * `delay` - async delay provider, e.g. from embassy or a wrapper around tokio's sleep
+/// * `ms_sleep` - timeout after each frame
+#[cfg(feature = "alloc")]
+pub async fn play_animation<D: AsyncMatrixDevice, T: DelayNs>(
+    display: &mut D,
+    mut animation: impl Animation,
+    display_count: usize,
+    delay: &mut T,
+    ms_sleep: u32,
+) -> Result<(), Error> {
+    check_display_count_of(display.module_count(), display_count)?;
+
+    let mut frame = Frame::new(display_count);
+    let mut shown: Option<Frame> = None;
+    while animation.next_frame(&mut frame) {
+        write_frame_changes(display, &frame, shown.as_ref(), display_count).await?;
+        shown = Some(frame.clone());
+        delay.delay_ms(ms_sleep).await;
+    }
+    Ok(())
+}
+
+/// Like [`write_frame`] but only sends what changed compared to the `previous` frame.
+#[cfg(feature = "alloc")]
+async fn write_frame_changes<D: AsyncMatrixDevice>(
+    display: &mut D,
+    frame: &Frame,
+    previous: Option<&Frame>,
+    display_count: usize,
+) -> Result<(), Error> {
+    for change in frame_changes(frame, previous, display_count) {
+        match change {
+            FrameChange::PowerOff => display.power_off().await?,
+            FrameChange::Rows(module, rows) => display.write_rows(module, rows).await?,
+            FrameChange::Intensity(module, intensity) => {
+                display.set_intensity(module, intensity).await?
+            }
+            FrameChange::PowerOn => display.power_on().await?,
+        }
+    }
+    display.flush().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "alloc")]
+    use crate::device::MemoryDevice;
+    use alloc::vec::Vec;
+    use core::convert::Infallible;
+    use core::future::Future;
+    use core::pin::pin;
+    use core::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+    use embedded_hal_async::spi::{ErrorType, Operation};
+
+    /// Polls the future until it is ready. The futures in these tests never wait.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        const VTABLE: RawWakerVTable = RawWakerVTable::new(
+            |_| RawWaker::new(core::ptr::null(), &VTABLE),
+            |_| {},
+            |_| {},
+            |_| {},
+        );
+        let waker = unsafe { Waker::from_raw(RawWaker::new(core::ptr::null(), &VTABLE)) };
+        let mut context = Context::from_waker(&waker);
+        let mut future = pin!(future);
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+                return output;
+            }
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    struct NoDelay;
+
+    #[cfg(feature = "alloc")]
+    impl DelayNs for NoDelay {
+        async fn delay_ns(&mut self, _ns: u32) {}
+    }
+
+    #[cfg(feature = "alloc")]
+    impl embedded_hal::blocking::delay::DelayMs<u32> for NoDelay {
+        fn delay_ms(&mut self, _ms: u32) {}
+    }
+
+    /// Records the data of each SPI transfer.
+    #[derive(Default)]
+    struct MockSpi(Vec<Vec<u8>>);
+
+    impl ErrorType for MockSpi {
+        type Error = Infallible;
+    }
+
+    impl SpiDevice for MockSpi {
+        async fn transaction(
+            &mut self,
+            operations: &mut [Operation<'_, u8>],
+        ) -> Result<(), Self::Error> {
+            for operation in operations {
+                if let Operation::Write(data) = operation {
+                    self.0.push(data.to_vec());
+                }
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_async_max7219_writes() {
+        let mut driver = AsyncMax7219::new(MockSpi::default(), 2).unwrap();
+        block_on(driver.power_on()).unwrap();
+        block_on(driver.set_intensity(1, 0x0A)).unwrap();
+        assert!(matches!(
+            block_on(driver.set_intensity(2, 0)),
+            Err(Error::InvalidModule(2))
+        ));
+        let spi = driver.into_inner();
+        assert_eq!(spi.0, [[0x0C, 1, 0x0C, 1], [0, 0, 0x0A, 0x0A]]);
+    }
+
+    #[test]
+    fn test_write_rows_respects_scan_limit() {
+        let mut driver = AsyncMax7219::new(MockSpi::default(), 1).unwrap();
+        block_on(driver.set_scan_limit(0, 3)).unwrap();
+        block_on(driver.write_rows(0, &[0xFF; 8])).unwrap();
+        assert_eq!(
+            driver.into_inner().0[1..],
+            [[0x01, 0xFF], [0x02, 0xFF], [0x03, 0xFF], [0x04, 0xFF]]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_play_animation_like_sync_version() {
+        let mut expected = MemoryDevice::new(2);
+        crate::prepare_display(&mut expected, 2, 0x0F).unwrap();
+        let animation = Scroller::new("HI", None).passes(1);
+        crate::play_animation_with_delay(&mut expected, animation, 2, &mut NoDelay, 10).unwrap();
+
+        let mut device = Blocking(MemoryDevice::new(2));
+        block_on(prepare_display(&mut device, 2, 0x0F)).unwrap();
+        let animation = Scroller::new("HI", None).passes(1);
+        block_on(play_animation(&mut device, animation, 2, &mut NoDelay, 10)).unwrap();
+        assert_eq!(device.0, expected);
+    }
+}
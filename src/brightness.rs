@@ -0,0 +1,252 @@
+//! Brightness animations via the intensity register, like fading in, fading out and "breathing".
+//! The MAX7219 has 16 intensity levels. The steps between them are gamma-corrected, so that the
+//! brightness appears to change linearly to the human eye.
+
+#[cfg(feature = "alloc")]
+use crate::animation::{Animation, Frame};
+#[cfg(feature = "alloc")]
+use crate::mappings::SingleDisplayData;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+/// Maximum value of the intensity register.
+pub const MAX_INTENSITY: u8 = 0x0F;
+
+/// Perceived brightness (`0..=1000`) of each intensity level. Level `k` has a duty cycle of
+/// `(2k + 1) / 32`; the perceived brightness is `duty ^ (1 / 2.2)`.
+const PERCEIVED_BRIGHTNESS: [u16; 16] = [
+    207, 341, 430, 501, 562, 615, 664, 709, 750, 789, 826, 861, 894, 926, 956, 986,
+];
+
+/// Returns the intensity level at `progress / total` of the way from `from` to `to`, so that the
+/// perceived brightness changes linearly. Levels are clamped to [`MAX_INTENSITY`].
+pub fn gamma_intensity(from: u8, to: u8, progress: usize, total: usize) -> u8 {
+    let from = i64::from(PERCEIVED_BRIGHTNESS[usize::from(from.min(MAX_INTENSITY))]);
+    let to = i64::from(PERCEIVED_BRIGHTNESS[usize::from(to.min(MAX_INTENSITY))]);
+    let (progress, total) = if total == 0 {
+        (1, 1)
+    } else {
+        (progress.min(total) as i64, total as i64)
+    };
+    let target = from + (to - from) * progress / total;
+    // pick the level with the closest perceived brightness
+    PERCEIVED_BRIGHTNESS
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, brightness)| (i64::from(**brightness) - target).abs())
+        .map(|(level, _)| level as u8)
+        .unwrap()
+}
+
+/// Returns the intensity level for a module with a reduced scan limit that appears about as bright
+/// as `intensity` on a module that shows all 8 rows. The MAX7219 multiplexes the rows up to the
+/// scan limit, so with fewer rows each row is lit longer and appears brighter.
+///
+/// According to the datasheet, with a scan limit of `2` or less (3 or fewer rows or digits),
+/// the digit drivers dissipate too much power. The resistor RSET must then be increased by the
+/// factor 1.7 (3 digits), 2.0 (2 digits) or 2.5 (1 digit); that can't be done via the intensity.
+pub fn scan_limit_intensity(intensity: u8, scan_limit: u8) -> u8 {
+    let rows = u32::from(scan_limit.min(7)) + 1;
+    // level `k` lights the LEDs for `(2k + 1) / 32` of the time slot of each row
+    let duty = u32::from(intensity.min(MAX_INTENSITY)) * 2 + 1;
+    // solve `(2k' + 1) / rows = (2k + 1) / 8` for `k'` and round
+    (duty * rows / 16) as u8
+}
+
+/// Sets the intensity of a single module or of all modules in `frame`.
+#[cfg(feature = "alloc")]
+fn set_intensity(frame: &mut Frame, module: Option<usize>, intensity: u8) {
+    match module {
+        None => frame.set_intensity(intensity),
+        Some(module) => {
+            if let Some(module_intensity) = frame.intensity.get_mut(module) {
+                *module_intensity = Some(intensity);
+            }
+        }
+    }
+}
+
+/// Fades the brightness in or out within `duration` frames.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone)]
+pub struct Fade {
+    from: u8,
+    to: u8,
+    duration: usize,
+    module: Option<usize>,
+    content: Option<Vec<SingleDisplayData>>,
+    step: usize,
+}
+
+#[cfg(feature = "alloc")]
+impl Fade {
+    /// Fades from `from` to `to`.
+    ///
+    /// * `from` - intensity at the beginning; value between `0x00` and `0x0F`
+    /// * `to` - intensity at the end; value between `0x00` and `0x0F`
+    /// * `duration` - duration in frames
+    pub const fn new(from: u8, to: u8, duration: usize) -> Self {
+        Self {
+            from,
+            to,
+            duration,
+            module: None,
+            content: None,
+            step: 0,
+        }
+    }
+
+    /// Fades from the lowest intensity to `max_intensity`.
+    pub const fn fade_in(max_intensity: u8, duration: usize) -> Self {
+        Self::new(0, max_intensity, duration)
+    }
+
+    /// Fades from `max_intensity` to the lowest intensity. Note that the lowest intensity is not
+    /// dark. Power the displays off afterwards to hide the content.
+    pub const fn fade_out(max_intensity: u8, duration: usize) -> Self {
+        Self::new(max_intensity, 0, duration)
+    }
+
+    /// Only changes the intensity of the module with the given index, e.g. to highlight it.
+    pub const fn module(mut self, module: usize) -> Self {
+        self.module = Some(module);
+        self
+    }
+
+    /// Sets the content shown during the fade. Without content, the content of the previous
+    /// frame stays on the display.
+    pub fn content(mut self, content: Vec<SingleDisplayData>) -> Self {
+        self.content = Some(content);
+        self
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Animation for Fade {
+    fn next_frame(&mut self, frame: &mut Frame) -> bool {
+        if self.step >= self.duration {
+            return false;
+        }
+        self.step += 1;
+        if let Some(content) = &self.content {
+            frame.set_data(content);
+        }
+        let intensity = gamma_intensity(self.from, self.to, self.step, self.duration);
+        set_intensity(frame, self.module, intensity);
+        true
+    }
+}
+
+/// Lets the brightness go up and down, like breathing. The brightness starts at the lowest value.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone)]
+pub struct Breathe {
+    min: u8,
+    max: u8,
+    period: usize,
+    times: Option<usize>,
+    module: Option<usize>,
+    step: usize,
+}
+
+#[cfg(feature = "alloc")]
+impl Breathe {
+    /// Creates an endless breathing animation.
+    ///
+    /// * `min` - lowest intensity; value between `0x00` and `0x0F`
+    /// * `max` - highest intensity; value between `0x00` and `0x0F`
+    /// * `period` - frames of one breath (up and down again)
+    pub const fn new(min: u8, max: u8, period: usize) -> Self {
+        Self {
+            min,
+            max,
+            period,
+            times: None,
+            module: None,
+            step: 0,
+        }
+    }
+
+    /// Stops after `times` breaths.
+    pub const fn times(mut self, times: usize) -> Self {
+        self.times = Some(times);
+        self
+    }
+
+    /// Only changes the intensity of the module with the given index, e.g. to highlight it.
+    pub const fn module(mut self, module: usize) -> Self {
+        self.module = Some(module);
+        self
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Animation for Breathe {
+    fn next_frame(&mut self, frame: &mut Frame) -> bool {
+        if self.period == 0
+            || self
+                .times
+                .map_or(false, |times| self.step >= times * self.period)
+        {
+            return false;
+        }
+        let half = self.period / 2;
+        let phase = self.step % self.period;
+        self.step += 1;
+
+        let intensity = if phase < half {
+            gamma_intensity(self.min, self.max, phase, half)
+        } else {
+            gamma_intensity(self.max, self.min, phase - half, self.period - half)
+        };
+        set_intensity(frame, self.module, intensity);
+        true
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_limit_intensity() {
+        assert_eq!(scan_limit_intensity(0x0F, 7), 0x0F);
+        assert_eq!(scan_limit_intensity(0x05, 7), 0x05);
+        assert_eq!(scan_limit_intensity(0x0F, 3), 0x07);
+        assert_eq!(scan_limit_intensity(0x08, 5), 0x06);
+        assert_eq!(scan_limit_intensity(0x00, 0), 0x00);
+    }
+
+    #[test]
+    fn test_gamma_intensity() {
+        assert_eq!(gamma_intensity(0, 15, 0, 10), 0);
+        assert_eq!(gamma_intensity(0, 15, 10, 10), 15);
+        assert_eq!(gamma_intensity(15, 0, 10, 10), 0);
+        // half the perceived brightness is reached at a lower level than 7
+        assert!(gamma_intensity(0, 15, 5, 10) < 7);
+        // steps are monotonic
+        let levels: Vec<_> = (0..=10).map(|i| gamma_intensity(0, 15, i, 10)).collect();
+        assert!(levels.windows(2).all(|w| w[0] <= w[1]));
+    }
+
+    #[test]
+    fn test_fade_single_module() {
+        let mut fade = Fade::fade_in(15, 4).module(1);
+        let mut frame = Frame::new(2);
+        while fade.next_frame(&mut frame) {}
+        assert_eq!(frame.intensity, [None, Some(15)]);
+    }
+
+    #[test]
+    fn test_breathe() {
+        let mut breathe = Breathe::new(0, 15, 4).times(1);
+        let mut frame = Frame::new(1);
+        let mut intensities = Vec::new();
+        while breathe.next_frame(&mut frame) {
+            intensities.push(frame.intensity[0].unwrap());
+        }
+        assert_eq!(intensities.len(), 4);
+        assert_eq!(intensities[0], 0);
+        assert_eq!(intensities[2], 15);
+    }
+}
@@ -0,0 +1,208 @@
+use crate::brightness::scan_limit_intensity;
+use crate::setup::setup_lines;
+use crate::{Display, Error, GpioChip, GpioLine, Max7219Adapter, Orientation};
+use alloc::string::String;
+
+/// Builder for a [`Display`] that is connected via GPIO pins. All settings are validated and
+/// applied by [`DisplayConfig::build`], which returns a display that is ready to use.
+///
+/// ```no_run
+/// use max_7219_led_matrix_util::{DisplayConfig, Orientation};
+///
+/// let mut display = DisplayConfig::new(4)
+///     .data_pin(12)
+///     .cs_pin(16)
+///     .clk_pin(21)
+///     .intensity(0x08)
+///     .orientation(Orientation::Rotate90)
+///     .build()
+///     .unwrap();
+/// display.show_moving_text_in_loop("HELLO", 50, None).unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DisplayConfig {
+    chip: GpioChip,
+    data_pin: Option<GpioLine>,
+    cs_pin: Option<GpioLine>,
+    clk_pin: Option<GpioLine>,
+    display_count: usize,
+    intensity: u8,
+    scan_limit: u8,
+    orientation: Orientation,
+    clock_half_period_us: u32,
+}
+
+impl DisplayConfig {
+    /// Creates a config for `display_count` chained displays on "/dev/gpiochip0" with the
+    /// highest intensity, all rows enabled and [`Orientation::Normal`]. The pins must be set.
+    pub fn new(display_count: usize) -> Self {
+        Self {
+            chip: GpioChip::Path(String::from("/dev/gpiochip0")),
+            data_pin: None,
+            cs_pin: None,
+            clk_pin: None,
+            display_count,
+            intensity: 0x0F,
+            scan_limit: 7,
+            orientation: Orientation::Normal,
+            clock_half_period_us: 0,
+        }
+    }
+
+    /// Sets the path of the GPIO chip.
+    pub fn chip(mut self, path: &str) -> Self {
+        self.chip = GpioChip::Path(String::from(path));
+        self
+    }
+
+    /// Sets the GPIO chip by its label, e.g. "pinctrl-bcm2711". See [`GpioChip::Label`].
+    pub fn chip_label(mut self, label: &str) -> Self {
+        self.chip = GpioChip::Label(String::from(label));
+        self
+    }
+
+    /// Sets the number of the GPIO pin used as data pin (DIN).
+    pub fn data_pin(mut self, pin: u32) -> Self {
+        self.data_pin = Some(GpioLine::Offset(pin));
+        self
+    }
+
+    /// Sets the data pin (DIN) by its line name, e.g. "GPIO12".
+    pub fn data_pin_name(mut self, name: &str) -> Self {
+        self.data_pin = Some(GpioLine::Name(String::from(name)));
+        self
+    }
+
+    /// Sets the number of the GPIO pin used as cs (chip select) pin.
+    pub fn cs_pin(mut self, pin: u32) -> Self {
+        self.cs_pin = Some(GpioLine::Offset(pin));
+        self
+    }
+
+    /// Sets the cs (chip select) pin by its line name, e.g. "GPIO16".
+    pub fn cs_pin_name(mut self, name: &str) -> Self {
+        self.cs_pin = Some(GpioLine::Name(String::from(name)));
+        self
+    }
+
+    /// Sets the number of the GPIO pin used as clock pin.
+    pub fn clk_pin(mut self, pin: u32) -> Self {
+        self.clk_pin = Some(GpioLine::Offset(pin));
+        self
+    }
+
+    /// Sets the clock pin by its line name, e.g. "GPIO21".
+    pub fn clk_pin_name(mut self, name: &str) -> Self {
+        self.clk_pin = Some(GpioLine::Name(String::from(name)));
+        self
+    }
+
+    /// Sets the brightness of all displays; value between `0x00` and `0x0F`.
+    pub const fn intensity(mut self, intensity: u8) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    /// Sets the index of the last row that is displayed; value between `0` and `7`. With a lower
+    /// scan limit, the intensity is reduced so that the brightness stays about the same; see
+    /// [`crate::brightness::scan_limit_intensity`] for the limits of that.
+    pub const fn scan_limit(mut self, scan_limit: u8) -> Self {
+        self.scan_limit = scan_limit;
+        self
+    }
+
+    /// Sets the rotation that is applied to the content of each display.
+    pub const fn orientation(mut self, orientation: Orientation) -> Self {
+        self.orientation = orientation;
+        self
+    }
+
+    /// Sets the minimum time in microseconds the clock pin is high or low. By default, the pins
+    /// are toggled as fast as possible, which can be too fast for long wires or level shifters
+    /// on fast SoCs. Try `1` to `5` if the displays show garbage. See the "benchmark" example.
+    pub const fn clock_half_period_us(mut self, half_period_us: u32) -> Self {
+        self.clock_half_period_us = half_period_us;
+        self
+    }
+
+    /// Sets up the driver, turns on and clears the displays and applies the config.
+    pub fn build(&self) -> Result<Display<Max7219Adapter>, Error> {
+        let data_pin = self
+            .data_pin
+            .as_ref()
+            .ok_or(Error::InvalidConfig("the data pin is not set"))?;
+        let cs_pin = self
+            .cs_pin
+            .as_ref()
+            .ok_or(Error::InvalidConfig("the cs pin is not set"))?;
+        let clk_pin = self
+            .clk_pin
+            .as_ref()
+            .ok_or(Error::InvalidConfig("the clk pin is not set"))?;
+        self.validate()?;
+
+        let mut display = setup_lines(
+            &self.chip,
+            self.display_count,
+            data_pin,
+            cs_pin,
+            clk_pin,
+            self.clock_half_period_us,
+        )?;
+        display.set_orientation(self.orientation);
+        display.prepare(scan_limit_intensity(self.intensity, self.scan_limit))?;
+        display.set_scan_limit(self.scan_limit)?;
+        Ok(display)
+    }
+
+    /// Checks the settings that don't depend on the hardware.
+    const fn validate(&self) -> Result<(), Error> {
+        if self.intensity > 0x0F {
+            return Err(Error::InvalidConfig("the intensity must be at most 0x0F"));
+        }
+        if self.scan_limit > 7 {
+            return Err(Error::InvalidConfig("the scan limit must be at most 7"));
+        }
+        crate::check_display_count(self.display_count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_pin() {
+        let config = DisplayConfig::new(4).data_pin(12).clk_pin(21);
+        assert!(matches!(
+            config.build(),
+            Err(Error::InvalidConfig("the cs pin is not set"))
+        ));
+    }
+
+    #[test]
+    fn test_unknown_chip_label() {
+        let config = DisplayConfig::new(4)
+            .chip_label("no-such-gpio-chip")
+            .data_pin_name("GPIO12")
+            .cs_pin_name("GPIO16")
+            .clk_pin_name("GPIO21");
+        match config.build() {
+            Err(Error::ChipNotFound(label)) => assert_eq!(label, "no-such-gpio-chip"),
+            // systems without a readable "/dev"
+            Err(Error::GpioOpen(_)) => {}
+            other => panic!("unexpected result: {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(DisplayConfig::new(4).validate().is_ok());
+        assert!(matches!(
+            DisplayConfig::new(0).validate(),
+            Err(Error::InvalidDisplayCount(0))
+        ));
+        assert!(DisplayConfig::new(4).intensity(0x10).validate().is_err());
+        assert!(DisplayConfig::new(4).scan_limit(8).validate().is_err());
+    }
+}
@@ -0,0 +1,472 @@
+//! The [`MatrixDevice`] trait decouples the rendering (frames, animations, effects) from the
+//! driver. It is implemented for the driver of the "max7219"-crate and for `MemoryDevice`,
+//! which only keeps the state of the displays in memory, e.g. for tests and simulations.
+//! `Combined` joins several independent chains into one.
+
+use crate::mappings::SingleDisplayData;
+#[cfg(feature = "alloc")]
+use crate::Display;
+use crate::{Error, LED_SQUARE_MATRIX_DIM, MAX_DISPLAYS};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use max7219::connectors::Connector;
+use max7219::{DecodeMode, MAX7219};
+
+/// A chain of MAX7219-powered displays (modules). Module 0 is the first display of the chain.
+pub trait MatrixDevice {
+    /// Writes the 8 rows of the module with the given index.
+    fn write_rows(&mut self, module: usize, rows: &SingleDisplayData) -> Result<(), Error>;
+
+    /// Sets the brightness of the module with the given index; value between `0x00` and `0x0F`.
+    fn set_intensity(&mut self, module: usize, intensity: u8) -> Result<(), Error>;
+
+    /// Powers on all modules.
+    fn power_on(&mut self) -> Result<(), Error>;
+
+    /// Powers off all modules. The modules keep their content.
+    fn power_off(&mut self) -> Result<(), Error>;
+
+    /// Sets the scan limit of the module with the given index, i.e. the index of the last row
+    /// (digit) that is displayed; value between `0` and `7`.
+    fn set_scan_limit(&mut self, module: usize, limit: u8) -> Result<(), Error>;
+
+    /// Sets the decode mode of the module with the given index.
+    fn set_decode_mode(&mut self, module: usize, mode: DecodeMode) -> Result<(), Error>;
+
+    /// Turns the display test mode of the module with the given index on or off. In test mode,
+    /// all LEDs are on with the highest intensity, regardless of the other registers.
+    fn set_display_test(&mut self, module: usize, on: bool) -> Result<(), Error>;
+
+    /// Turns all pixels of the module with the given index off.
+    fn clear(&mut self, module: usize) -> Result<(), Error> {
+        self.write_rows(module, &[0; LED_SQUARE_MATRIX_DIM])
+    }
+
+    /// Called after a whole frame was written. Devices that don't send each write to the
+    /// hardware immediately, like simulators, can update their output here.
+    fn flush(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Count of the modules of the chain, if the device knows it. [`crate::Display::new`]
+    /// rejects other display counts.
+    fn module_count(&self) -> Option<usize> {
+        None
+    }
+}
+
+impl<D: MatrixDevice + ?Sized> MatrixDevice for &mut D {
+    fn write_rows(&mut self, module: usize, rows: &SingleDisplayData) -> Result<(), Error> {
+        (**self).write_rows(module, rows)
+    }
+
+    fn set_intensity(&mut self, module: usize, intensity: u8) -> Result<(), Error> {
+        (**self).set_intensity(module, intensity)
+    }
+
+    fn power_on(&mut self) -> Result<(), Error> {
+        (**self).power_on()
+    }
+
+    fn power_off(&mut self) -> Result<(), Error> {
+        (**self).power_off()
+    }
+
+    fn set_scan_limit(&mut self, module: usize, limit: u8) -> Result<(), Error> {
+        (**self).set_scan_limit(module, limit)
+    }
+
+    fn set_decode_mode(&mut self, module: usize, mode: DecodeMode) -> Result<(), Error> {
+        (**self).set_decode_mode(module, mode)
+    }
+
+    fn set_display_test(&mut self, module: usize, on: bool) -> Result<(), Error> {
+        (**self).set_display_test(module, on)
+    }
+
+    fn clear(&mut self, module: usize) -> Result<(), Error> {
+        (**self).clear(module)
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        (**self).flush()
+    }
+
+    fn module_count(&self) -> Option<usize> {
+        (**self).module_count()
+    }
+}
+
+impl<C: Connector> MatrixDevice for MAX7219<C> {
+    fn write_rows(&mut self, module: usize, rows: &SingleDisplayData) -> Result<(), Error> {
+        check_module(module)?;
+        Ok(self.write_raw(module, rows)?)
+    }
+
+    fn set_intensity(&mut self, module: usize, intensity: u8) -> Result<(), Error> {
+        check_module(module)?;
+        Ok(Self::set_intensity(self, module, intensity)?)
+    }
+
+    fn power_on(&mut self) -> Result<(), Error> {
+        Ok(Self::power_on(self)?)
+    }
+
+    fn power_off(&mut self) -> Result<(), Error> {
+        Ok(Self::power_off(self)?)
+    }
+
+    fn set_scan_limit(&mut self, module: usize, limit: u8) -> Result<(), Error> {
+        check_module(module)?;
+        // the "max7219"-crate doesn't provide access to the scan limit register; it sets the
+        // scan limit to 7 during its initialization
+        if limit == 7 {
+            Ok(())
+        } else {
+            Err(Error::Unsupported)
+        }
+    }
+
+    fn set_decode_mode(&mut self, module: usize, mode: DecodeMode) -> Result<(), Error> {
+        check_module(module)?;
+        Ok(Self::set_decode_mode(self, module, mode)?)
+    }
+
+    fn set_display_test(&mut self, module: usize, on: bool) -> Result<(), Error> {
+        check_module(module)?;
+        Ok(self.test(module, on)?)
+    }
+
+    fn clear(&mut self, module: usize) -> Result<(), Error> {
+        check_module(module)?;
+        Ok(self.clear_display(module)?)
+    }
+}
+
+/// The "max7219"-crate panics for modules after [`MAX_DISPLAYS`].
+const fn check_module(module: usize) -> Result<(), Error> {
+    if module < MAX_DISPLAYS {
+        Ok(())
+    } else {
+        Err(Error::InvalidModule(module))
+    }
+}
+
+/// Several independent chains, e.g. with separate CS lines, as one chain. The modules of the
+/// first chain come first. Each chain is limited to [`crate::MAX_DISPLAYS`] modules by its
+/// [`Display`]; use the combined device with a [`Display`] for [`Combined::module_count`]
+/// modules, which may be more than that.
+#[cfg(feature = "alloc")]
+#[derive(Debug)]
+pub struct Combined<D> {
+    /// The device and the module count of each chain.
+    chains: Vec<(D, usize)>,
+}
+
+#[cfg(feature = "alloc")]
+impl<D: MatrixDevice> Combined<D> {
+    /// Joins the chains in the given order. Only the devices and the display counts are used,
+    /// set the orientation and the scan limit on the combined display.
+    pub fn new(chains: Vec<Display<D>>) -> Self {
+        let chains = chains
+            .into_iter()
+            .map(|display| {
+                let display_count = display.display_count();
+                (display.into_inner(), display_count)
+            })
+            .collect();
+        Self { chains }
+    }
+
+    /// Count of the modules of all chains.
+    pub fn module_count(&self) -> usize {
+        self.chains.iter().map(|(_, count)| count).sum()
+    }
+
+    /// Returns the devices of the chains.
+    pub fn into_inner(self) -> Vec<D> {
+        self.chains.into_iter().map(|(device, _)| device).collect()
+    }
+
+    /// The device of the chain with the module and the index of the module in that chain.
+    fn chain(&mut self, module: usize) -> Result<(&mut D, usize), Error> {
+        let mut first = 0;
+        for (device, count) in &mut self.chains {
+            if module < first + *count {
+                return Ok((device, module - first));
+            }
+            first += *count;
+        }
+        Err(Error::InvalidModule(module))
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<D: MatrixDevice> MatrixDevice for Combined<D> {
+    fn write_rows(&mut self, module: usize, rows: &SingleDisplayData) -> Result<(), Error> {
+        let (device, module) = self.chain(module)?;
+        device.write_rows(module, rows)
+    }
+
+    fn set_intensity(&mut self, module: usize, intensity: u8) -> Result<(), Error> {
+        let (device, module) = self.chain(module)?;
+        device.set_intensity(module, intensity)
+    }
+
+    fn power_on(&mut self) -> Result<(), Error> {
+        self.chains
+            .iter_mut()
+            .try_for_each(|(device, _)| device.power_on())
+    }
+
+    fn power_off(&mut self) -> Result<(), Error> {
+        self.chains
+            .iter_mut()
+            .try_for_each(|(device, _)| device.power_off())
+    }
+
+    fn set_scan_limit(&mut self, module: usize, limit: u8) -> Result<(), Error> {
+        let (device, module) = self.chain(module)?;
+        device.set_scan_limit(module, limit)
+    }
+
+    fn set_decode_mode(&mut self, module: usize, mode: DecodeMode) -> Result<(), Error> {
+        let (device, module) = self.chain(module)?;
+        device.set_decode_mode(module, mode)
+    }
+
+    fn set_display_test(&mut self, module: usize, on: bool) -> Result<(), Error> {
+        let (device, module) = self.chain(module)?;
+        device.set_display_test(module, on)
+    }
+
+    fn clear(&mut self, module: usize) -> Result<(), Error> {
+        let (device, module) = self.chain(module)?;
+        device.clear(module)
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        self.chains
+            .iter_mut()
+            .try_for_each(|(device, _)| device.flush())
+    }
+
+    fn module_count(&self) -> Option<usize> {
+        Some(Self::module_count(self))
+    }
+}
+
+/// State of a single module of a `MemoryDevice`.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModuleState {
+    /// Content of the digit registers.
+    pub rows: SingleDisplayData,
+    /// Value of the intensity register.
+    pub intensity: u8,
+    /// Value of the scan limit register.
+    pub scan_limit: u8,
+    /// Value of the decode mode register.
+    pub decode_mode: u8,
+    /// Whether the display test mode is on.
+    pub display_test: bool,
+}
+
+#[cfg(feature = "alloc")]
+impl Default for ModuleState {
+    /// The state after the initialization by the "max7219"-crate.
+    fn default() -> Self {
+        Self {
+            rows: [0; LED_SQUARE_MATRIX_DIM],
+            intensity: 0,
+            scan_limit: 7,
+            decode_mode: DecodeMode::NoDecode as u8,
+            display_test: false,
+        }
+    }
+}
+
+/// A [`MatrixDevice`] that keeps the state of all modules in memory. Useful to test and simulate
+/// animations without hardware.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryDevice {
+    /// State of each module.
+    pub modules: Vec<ModuleState>,
+    /// Whether the modules are powered on.
+    pub power: bool,
+}
+
+#[cfg(feature = "alloc")]
+impl MemoryDevice {
+    /// Creates a powered off device with `module_count` empty modules.
+    pub fn new(module_count: usize) -> Self {
+        Self {
+            modules: vec![ModuleState::default(); module_count],
+            power: false,
+        }
+    }
+
+    /// Returns the content of all modules.
+    pub fn rows(&self) -> Vec<SingleDisplayData> {
+        self.modules.iter().map(|module| module.rows).collect()
+    }
+
+    fn module(&mut self, module: usize) -> Result<&mut ModuleState, Error> {
+        self.modules
+            .get_mut(module)
+            .ok_or(Error::InvalidModule(module))
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl MatrixDevice for MemoryDevice {
+    fn write_rows(&mut self, module: usize, rows: &SingleDisplayData) -> Result<(), Error> {
+        self.module(module)?.rows = *rows;
+        Ok(())
+    }
+
+    fn set_intensity(&mut self, module: usize, intensity: u8) -> Result<(), Error> {
+        self.module(module)?.intensity = intensity;
+        Ok(())
+    }
+
+    fn power_on(&mut self) -> Result<(), Error> {
+        self.power = true;
+        Ok(())
+    }
+
+    fn power_off(&mut self) -> Result<(), Error> {
+        self.power = false;
+        Ok(())
+    }
+
+    fn set_scan_limit(&mut self, module: usize, limit: u8) -> Result<(), Error> {
+        self.module(module)?.scan_limit = limit;
+        Ok(())
+    }
+
+    fn set_decode_mode(&mut self, module: usize, mode: DecodeMode) -> Result<(), Error> {
+        self.module(module)?.decode_mode = mode as u8;
+        Ok(())
+    }
+
+    fn set_display_test(&mut self, module: usize, on: bool) -> Result<(), Error> {
+        self.module(module)?.display_test = on;
+        Ok(())
+    }
+
+    fn module_count(&self) -> Option<usize> {
+        Some(self.modules.len())
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::*;
+    use crate::animation::Frame;
+    use crate::{prepare_display, write_frame};
+
+    #[test]
+    fn test_prepare_display_and_write_frame() {
+        let mut device = MemoryDevice::new(2);
+        device.write_rows(1, &[0xFF; 8]).unwrap();
+        prepare_display(&mut device, 2, 0x0A).unwrap();
+        assert!(device.power);
+        assert_eq!(device.rows(), [[0; 8], [0; 8]]);
+        assert!(device.modules.iter().all(|module| module.intensity == 0x0A));
+
+        let mut frame = Frame::new(2);
+        frame.data[0] = [0b1000_0001; 8];
+        frame.intensity[1] = Some(3);
+        frame.power = Some(false);
+        write_frame(&mut device, &frame, 2).unwrap();
+        assert!(!device.power);
+        assert_eq!(device.rows(), [[0b1000_0001; 8], [0; 8]]);
+        assert_eq!(device.modules[0].intensity, 0x0A);
+        assert_eq!(device.modules[1].intensity, 3);
+    }
+
+    #[test]
+    fn test_combined_chains() {
+        let chains = vec![
+            Display::new(MemoryDevice::new(1), 1).unwrap(),
+            Display::new(MemoryDevice::new(2), 2).unwrap(),
+        ];
+        let combined = Combined::new(chains);
+        assert_eq!(combined.module_count(), 3);
+        let mut display = Display::new(combined, 3).unwrap();
+        display.prepare(0x0F).unwrap();
+        let mut frame = Frame::new(3);
+        frame.data[2] = [0xFF; 8];
+        display.write_frame(&frame).unwrap();
+        assert!(matches!(
+            display.device_mut().set_intensity(3, 0),
+            Err(Error::InvalidModule(3))
+        ));
+
+        let chains = display.into_inner().into_inner();
+        assert!(chains.iter().all(|chain| chain.power));
+        assert_eq!(chains[0].rows(), [[0; 8]]);
+        assert_eq!(chains[1].rows(), [[0; 8], [0xFF; 8]]);
+    }
+
+    #[test]
+    fn test_combined_chains_beyond_max_displays() {
+        let chains = vec![
+            Display::new(MemoryDevice::new(MAX_DISPLAYS), MAX_DISPLAYS).unwrap(),
+            Display::new(MemoryDevice::new(MAX_DISPLAYS), MAX_DISPLAYS).unwrap(),
+        ];
+        let mut display = Display::new(Combined::new(chains), 2 * MAX_DISPLAYS).unwrap();
+        display.prepare(0x0F).unwrap();
+        let mut frame = Frame::new(2 * MAX_DISPLAYS);
+        frame.data[2 * MAX_DISPLAYS - 1] = [0xFF; 8];
+        display.write_frame(&frame).unwrap();
+        let chains = display.into_inner().into_inner();
+        assert_eq!(chains[1].modules[MAX_DISPLAYS - 1].rows, [0xFF; 8]);
+
+        let chains = vec![Display::new(MemoryDevice::new(2), 2).unwrap()];
+        assert!(matches!(
+            crate::prepare_display(&mut Combined::new(chains), 3, 0x0F),
+            Err(Error::InvalidDisplayCount(3))
+        ));
+    }
+
+    #[test]
+    fn test_memory_device_invalid_module() {
+        let mut device = MemoryDevice::new(1);
+        assert!(matches!(
+            device.set_intensity(1, 0),
+            Err(Error::InvalidModule(1))
+        ));
+    }
+
+    /// A pin that does nothing.
+    struct NoPin;
+
+    impl embedded_hal::digital::v2::OutputPin for NoPin {
+        type Error = core::convert::Infallible;
+
+        fn set_low(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_max7219_rejects_invalid_modules() {
+        let mut device = MAX7219::from_pins(2, NoPin, NoPin, NoPin).unwrap();
+        MatrixDevice::set_intensity(&mut device, 1, 0x0A).unwrap();
+        assert!(matches!(
+            device.write_rows(MAX_DISPLAYS, &[0xFF; 8]),
+            Err(Error::InvalidModule(MAX_DISPLAYS))
+        ));
+        assert!(matches!(
+            MatrixDevice::clear(&mut device, 9),
+            Err(Error::InvalidModule(9))
+        ));
+    }
+}
@@ -0,0 +1,670 @@
+#[cfg(feature = "alloc")]
+use crate::animation::{Animation, Frame, Scroller};
+use crate::device::MatrixDevice;
+use crate::mappings::SingleDisplayData;
+use crate::matrix::Matrix;
+#[cfg(feature = "alloc")]
+use crate::refresh::{RefreshInterval, Refreshing};
+use crate::{check_display_count_of, transpose_single_display_data, Error};
+#[cfg(feature = "alloc")]
+use crate::{play, write_frame_changes};
+use core::ops::{Deref, DerefMut};
+use embedded_hal::blocking::delay::DelayMs;
+use max7219::DecodeMode;
+#[cfg(feature = "std")]
+use std::{thread::sleep, time::Duration};
+
+/// Clockwise rotation of the content of each display. Useful if the modules are mounted rotated,
+/// as on some boards with 4 chained displays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Orientation {
+    /// The content is not rotated.
+    Normal,
+    /// The content is rotated by 90 degrees.
+    Rotate90,
+    /// The content is rotated by 180 degrees.
+    Rotate180,
+    /// The content is rotated by 270 degrees.
+    Rotate270,
+}
+
+impl Default for Orientation {
+    fn default() -> Self {
+        Self::Normal
+    }
+}
+
+impl Orientation {
+    /// Rotates the content of a single display.
+    pub fn apply(self, data: &SingleDisplayData) -> SingleDisplayData {
+        let mut rotated = match self {
+            Self::Normal | Self::Rotate180 => *data,
+            Self::Rotate90 | Self::Rotate270 => transpose_single_display_data(data),
+        };
+        if matches!(self, Self::Rotate90 | Self::Rotate180) {
+            rotated.iter_mut().for_each(|row| *row = row.reverse_bits());
+        }
+        if matches!(self, Self::Rotate180 | Self::Rotate270) {
+            rotated.reverse();
+        }
+        rotated
+    }
+}
+
+/// A [`MatrixDevice`] together with the count of its displays. The count is validated once
+/// and used by all operations, hence it can't differ between calls.
+#[derive(Debug)]
+pub struct Display<D: MatrixDevice> {
+    device: D,
+    display_count: usize,
+    orientation: Orientation,
+    scan_limit: u8,
+    known: KnownRegisters,
+}
+
+/// The registers that were written to all modules through the [`Display`], so that
+/// [`Display::with_refresh`] only re-sends what it can vouch for. Writes to
+/// [`Display::device_mut`] are not tracked.
+#[derive(Debug, Clone, Copy, Default)]
+struct KnownRegisters {
+    /// Whether all modules use no decode mode, as set by [`Display::prepare`].
+    no_decode: bool,
+    /// The last intensity and the count of modules, from module 0 on, that have it. Frames set
+    /// the intensity module by module, and not necessarily of all modules.
+    intensity: Option<(u8, usize)>,
+    /// Whether the displays are on; `None` if unknown.
+    power: Option<bool>,
+}
+
+impl KnownRegisters {
+    fn set_intensity(&mut self, module: usize, intensity: u8) {
+        self.intensity = match self.intensity {
+            _ if module == 0 => Some((intensity, 1)),
+            Some((known, modules)) if known == intensity && module <= modules => {
+                Some((known, modules.max(module + 1)))
+            }
+            _ => None,
+        };
+    }
+
+    /// The intensity of all `display_count` modules, if it is known.
+    #[cfg(feature = "alloc")]
+    const fn intensity(&self, display_count: usize) -> Option<u8> {
+        match self.intensity {
+            Some((intensity, modules)) if modules >= display_count => Some(intensity),
+            _ => None,
+        }
+    }
+}
+
+impl<D: MatrixDevice> Display<D> {
+    /// Creates a display with `display_count` chained displays. Fails with
+    /// [`Error::InvalidDisplayCount`] if the count is `0` or differs from
+    /// [`MatrixDevice::module_count`]. Without a module count, the count must not be greater
+    /// than [`crate::MAX_DISPLAYS`]; devices with a module count check that themselves, e.g.
+    /// each chain of a `Combined` device.
+    ///
+    /// * `device` - the display device, e.g. the Max7219 display driver
+    /// * `display_count` - count of displays connected to the MAX7219
+    pub fn new(device: D, display_count: usize) -> Result<Self, Error> {
+        check_display_count_of(device.module_count(), display_count)?;
+        if matches!(device.module_count(), Some(count) if count != display_count) {
+            return Err(Error::InvalidDisplayCount(display_count));
+        }
+        Ok(Self {
+            device,
+            display_count,
+            orientation: Orientation::Normal,
+            scan_limit: 7,
+            known: KnownRegisters::default(),
+        })
+    }
+
+    /// The rotation that is applied to the content of each display.
+    pub fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+
+    /// Sets the rotation that is applied to the content of each display.
+    pub fn set_orientation(&mut self, orientation: Orientation) {
+        self.orientation = orientation;
+    }
+
+    /// The index of the last row that is displayed by each module.
+    pub fn scan_limit(&self) -> u8 {
+        self.scan_limit
+    }
+
+    /// Sets the index of the last row that is displayed by each module; value between `0` and
+    /// `7`. Useful for boards that don't use all rows, like 4- or 6-digit 7-segment displays.
+    /// The other rows are not shown, and the shown rows appear brighter; see
+    /// [`crate::brightness::scan_limit_intensity`].
+    pub fn set_scan_limit(&mut self, scan_limit: u8) -> Result<(), Error> {
+        if scan_limit > 7 {
+            return Err(Error::InvalidConfig("the scan limit must be at most 7"));
+        }
+        for module in 0..self.display_count {
+            self.device.set_scan_limit(module, scan_limit)?;
+        }
+        self.scan_limit = scan_limit;
+        Ok(())
+    }
+
+    /// Count of displays connected to the MAX7219.
+    pub fn display_count(&self) -> usize {
+        self.display_count
+    }
+
+    /// The underlying device.
+    pub fn device(&self) -> &D {
+        &self.device
+    }
+
+    /// The underlying device.
+    pub fn device_mut(&mut self) -> &mut D {
+        &mut self.device
+    }
+
+    /// Returns the underlying device.
+    pub fn into_inner(self) -> D {
+        self.device
+    }
+
+    /// Turns on the displays, clears them and sets the brightness. See
+    /// [`crate::prepare_display`].
+    ///
+    /// * `intensity` - brightness for the display; value between `0x00` and `0x0F`
+    pub fn prepare(&mut self, intensity: u8) -> Result<(), Error> {
+        crate::prepare_display(&mut self.device, self.display_count, intensity)?;
+        self.known = KnownRegisters {
+            no_decode: true,
+            intensity: Some((intensity, self.display_count)),
+            power: Some(true),
+        };
+        Ok(())
+    }
+
+    /// Writes a single [`Frame`] to the displays. See [`crate::write_frame`].
+    #[cfg(feature = "alloc")]
+    pub fn write_frame(&mut self, frame: &Frame) -> Result<(), Error> {
+        let display_count = self.display_count;
+        write_frame_changes(&mut self.oriented(), frame, None, display_count)
+    }
+
+    /// Plays an [`Animation`] until it is finished. See `play_animation()`.
+    ///
+    /// * `animation` - the animation to play
+    /// * `ms_sleep` - timeout after each frame
+    #[cfg(feature = "std")]
+    pub fn play_animation(
+        &mut self,
+        animation: impl Animation,
+        ms_sleep: u64,
+    ) -> Result<(), Error> {
+        let display_count = self.display_count;
+        play(&mut self.oriented(), animation, display_count, || {
+            sleep(Duration::from_millis(ms_sleep))
+        })
+    }
+
+    /// Like `play_animation()` but waits with `delay` after each frame.
+    ///
+    /// * `animation` - the animation to play
+    /// * `delay` - delay provider, e.g. from the HAL of the microcontroller
+    /// * `ms_sleep` - timeout after each frame
+    #[cfg(feature = "alloc")]
+    pub fn play_animation_with_delay<T: DelayMs<u32>>(
+        &mut self,
+        animation: impl Animation,
+        delay: &mut T,
+        ms_sleep: u32,
+    ) -> Result<(), Error> {
+        let display_count = self.display_count;
+        play(&mut self.oriented(), animation, display_count, || {
+            delay.delay_ms(ms_sleep)
+        })
+    }
+
+    /// Shows a moving text in loop. This only returns if writing to the display fails.
+    /// See `show_moving_text_in_loop()`.
+    ///
+    /// * `text` - the text to display
+    /// * `ms_sleep` - timeout after each iteration
+    /// * `gap_width` - (optional) set's the maximum width/count of empty cols between characters.
+    #[cfg(feature = "std")]
+    pub fn show_moving_text_in_loop(
+        &mut self,
+        text: &str,
+        ms_sleep: u64,
+        gap_width: Option<usize>,
+    ) -> Result<(), Error> {
+        self.play_animation(Scroller::new(text, gap_width), ms_sleep)
+    }
+
+    /// Like `show_moving_text_in_loop()` but waits with `delay` after each iteration.
+    ///
+    /// * `text` - the text to display
+    /// * `delay` - delay provider, e.g. from the HAL of the microcontroller
+    /// * `ms_sleep` - timeout after each iteration
+    /// * `gap_width` - (optional) set's the maximum width/count of empty cols between characters.
+    #[cfg(feature = "alloc")]
+    pub fn show_moving_text_in_loop_with_delay<T: DelayMs<u32>>(
+        &mut self,
+        text: &str,
+        delay: &mut T,
+        ms_sleep: u32,
+        gap_width: Option<usize>,
+    ) -> Result<(), Error> {
+        self.play_animation_with_delay(Scroller::new(text, gap_width), delay, ms_sleep)
+    }
+
+    /// Runs the self test that checks the wiring order, the orientation and for dead LEDs. Each
+    /// step is shown for `ms_step` milliseconds. See [`crate::self_test`].
+    #[cfg(feature = "std")]
+    pub fn self_test(&mut self, ms_step: u64) -> Result<(), Error> {
+        let display_count = self.display_count;
+        crate::self_test::run(&mut self.oriented(), display_count, || {
+            sleep(Duration::from_millis(ms_step))
+        })
+    }
+
+    /// Like `self_test()` but waits with `delay` after each step.
+    ///
+    /// * `delay` - delay provider, e.g. from the HAL of the microcontroller
+    /// * `ms_step` - time each step is shown
+    #[cfg(feature = "alloc")]
+    pub fn self_test_with_delay<T: DelayMs<u32>>(
+        &mut self,
+        delay: &mut T,
+        ms_step: u32,
+    ) -> Result<(), Error> {
+        let display_count = self.display_count;
+        crate::self_test::run(&mut self.oriented(), display_count, || {
+            delay.delay_ms(ms_step)
+        })
+    }
+
+    /// Writes the content of `N` displays without a heap. Fails with
+    /// [`Error::InvalidDisplayCount`] if `N` differs from the display count. See
+    /// [`crate::write_display_data`].
+    pub fn write_display_data<const N: usize>(
+        &mut self,
+        data: &[SingleDisplayData; N],
+    ) -> Result<(), Error> {
+        self.check_const_display_count(N)?;
+        crate::write_display_data(&mut self.oriented(), data)
+    }
+
+    /// Like `show_moving_text_in_loop_with_buffer()` but without a buffer; the memory use doesn't
+    /// grow with the length of the text. `N` must be the display count. See
+    /// [`crate::show_moving_text_in_loop_lazy`].
+    ///
+    /// * `text` - the text to display
+    /// * `delay` - delay provider, e.g. from the HAL of the microcontroller
+    /// * `ms_sleep` - timeout after each iteration
+    /// * `gap_width` - (optional) set's the maximum width/count of empty cols between characters.
+    pub fn show_moving_text_in_loop_lazy<T: DelayMs<u32>, const N: usize>(
+        &mut self,
+        text: &str,
+        delay: &mut T,
+        ms_sleep: u32,
+        gap_width: Option<usize>,
+    ) -> Result<(), Error> {
+        self.check_const_display_count(N)?;
+        crate::show_moving_text_in_loop_lazy::<_, _, N>(
+            &mut self.oriented(),
+            text,
+            delay,
+            ms_sleep,
+            gap_width,
+        )
+    }
+
+    /// Writes `matrix` to the displays. Fails with [`Error::InvalidDisplayCount`] if `N` differs
+    /// from the display count.
+    pub fn write_matrix<const N: usize>(&mut self, matrix: &Matrix<N>) -> Result<(), Error> {
+        self.check_const_display_count(N)?;
+        matrix.write(&mut self.oriented())
+    }
+
+    /// Plays a sequence of [`Matrix<N>`](Matrix) without a heap. Fails with
+    /// [`Error::InvalidDisplayCount`] if `N` differs from the display count. See
+    /// [`crate::play_matrices`].
+    ///
+    /// * `matrices` - the content of each frame
+    /// * `delay` - delay provider, e.g. from the HAL of the microcontroller
+    /// * `ms_sleep` - timeout after each frame
+    pub fn play_matrices<T: DelayMs<u32>, const N: usize>(
+        &mut self,
+        matrices: impl IntoIterator<Item = Matrix<N>>,
+        delay: &mut T,
+        ms_sleep: u32,
+    ) -> Result<(), Error> {
+        self.check_const_display_count(N)?;
+        crate::play_matrices(&mut self.oriented(), matrices, delay, ms_sleep)
+    }
+
+    /// Like `show_moving_text_in_loop_with_delay()` but without a heap. `N` must be the display
+    /// count. See [`crate::show_moving_text_in_loop_with_buffer`].
+    ///
+    /// * `text` - the text to display
+    /// * `buffer` - memory for the encoded text; one display more than `text` has characters
+    /// * `delay` - delay provider, e.g. from the HAL of the microcontroller
+    /// * `ms_sleep` - timeout after each iteration
+    /// * `gap_width` - (optional) set's the maximum width/count of empty cols between characters.
+    pub fn show_moving_text_in_loop_with_buffer<T: DelayMs<u32>, const N: usize>(
+        &mut self,
+        text: &str,
+        buffer: &mut [SingleDisplayData],
+        delay: &mut T,
+        ms_sleep: u32,
+        gap_width: Option<usize>,
+    ) -> Result<(), Error> {
+        self.check_const_display_count(N)?;
+        crate::show_moving_text_in_loop_with_buffer::<_, _, N>(
+            &mut self.oriented(),
+            text,
+            buffer,
+            delay,
+            ms_sleep,
+            gap_width,
+        )
+    }
+
+    /// Clears all displays and powers them off.
+    pub fn shutdown(&mut self) -> Result<(), Error> {
+        for module in 0..self.display_count {
+            self.device.clear(module)?;
+        }
+        self.device.power_off()?;
+        self.known.power = Some(false);
+        self.device.flush()
+    }
+
+    /// Returns a handle that calls [`Display::shutdown`] when it is dropped, so that the
+    /// displays don't keep showing the last frame when the program ends.
+    pub fn shutdown_on_drop(self) -> ShutdownGuard<D> {
+        ShutdownGuard {
+            display: Some(self),
+        }
+    }
+
+    /// Re-sends the config registers and the content of all modules at the given interval, to
+    /// recover from glitches on long cables. See [`crate::refresh`]. The scan limit and the
+    /// decode mode, intensity and power state that were written through the display, e.g. by
+    /// [`Display::prepare`] or by frames, are written through the refresh once, so that they are
+    /// re-sent from then on. The content is re-sent once it was written again.
+    #[cfg(feature = "alloc")]
+    pub fn with_refresh(self, interval: RefreshInterval) -> Result<Display<Refreshing<D>>, Error> {
+        let mut device = Refreshing::new(self.device, self.display_count, interval);
+        let intensity = self.known.intensity(self.display_count);
+        for module in 0..self.display_count {
+            device.set_scan_limit(module, self.scan_limit)?;
+            if self.known.no_decode {
+                device.set_decode_mode(module, DecodeMode::NoDecode)?;
+            }
+            if let Some(intensity) = intensity {
+                device.set_intensity(module, intensity)?;
+            }
+        }
+        match self.known.power {
+            Some(true) => device.power_on()?,
+            Some(false) => device.power_off()?,
+            None => {}
+        }
+        Ok(Display {
+            device,
+            display_count: self.display_count,
+            orientation: self.orientation,
+            scan_limit: self.scan_limit,
+            known: self.known,
+        })
+    }
+
+    fn check_const_display_count(&self, display_count: usize) -> Result<(), Error> {
+        if display_count == self.display_count {
+            Ok(())
+        } else {
+            Err(Error::InvalidDisplayCount(display_count))
+        }
+    }
+
+    fn oriented(&mut self) -> Oriented<'_, D> {
+        Oriented {
+            device: &mut self.device,
+            orientation: self.orientation,
+            known: &mut self.known,
+        }
+    }
+}
+
+/// A [`Display`] that is cleared and powered off when it is dropped. Errors during the shutdown
+/// are ignored; call [`Display::shutdown`] to handle them. See [`Display::shutdown_on_drop`].
+#[derive(Debug)]
+pub struct ShutdownGuard<D: MatrixDevice> {
+    /// Always `Some` until [`ShutdownGuard::into_inner`] takes it.
+    display: Option<Display<D>>,
+}
+
+impl<D: MatrixDevice> ShutdownGuard<D> {
+    /// Returns the display without shutting it down.
+    pub fn into_inner(mut self) -> Display<D> {
+        self.display.take().unwrap()
+    }
+}
+
+impl<D: MatrixDevice> Deref for ShutdownGuard<D> {
+    type Target = Display<D>;
+
+    fn deref(&self) -> &Self::Target {
+        self.display.as_ref().unwrap()
+    }
+}
+
+impl<D: MatrixDevice> DerefMut for ShutdownGuard<D> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.display.as_mut().unwrap()
+    }
+}
+
+impl<D: MatrixDevice> Drop for ShutdownGuard<D> {
+    fn drop(&mut self) {
+        if let Some(display) = &mut self.display {
+            let _ = display.shutdown();
+        }
+    }
+}
+
+/// Rotates the content of each display before it is written to the device and keeps the
+/// [`KnownRegisters`] of the display up to date.
+struct Oriented<'a, D> {
+    device: &'a mut D,
+    orientation: Orientation,
+    known: &'a mut KnownRegisters,
+}
+
+impl<D: MatrixDevice> MatrixDevice for Oriented<'_, D> {
+    fn write_rows(&mut self, module: usize, rows: &SingleDisplayData) -> Result<(), Error> {
+        self.device
+            .write_rows(module, &self.orientation.apply(rows))
+    }
+
+    fn set_intensity(&mut self, module: usize, intensity: u8) -> Result<(), Error> {
+        self.device.set_intensity(module, intensity)?;
+        self.known.set_intensity(module, intensity);
+        Ok(())
+    }
+
+    fn power_on(&mut self) -> Result<(), Error> {
+        self.device.power_on()?;
+        self.known.power = Some(true);
+        Ok(())
+    }
+
+    fn power_off(&mut self) -> Result<(), Error> {
+        self.device.power_off()?;
+        self.known.power = Some(false);
+        Ok(())
+    }
+
+    fn set_scan_limit(&mut self, module: usize, limit: u8) -> Result<(), Error> {
+        self.device.set_scan_limit(module, limit)
+    }
+
+    fn set_decode_mode(&mut self, module: usize, mode: DecodeMode) -> Result<(), Error> {
+        if !matches!(mode, DecodeMode::NoDecode) {
+            self.known.no_decode = false;
+        }
+        self.device.set_decode_mode(module, mode)
+    }
+
+    fn set_display_test(&mut self, module: usize, on: bool) -> Result<(), Error> {
+        self.device.set_display_test(module, on)
+    }
+
+    fn clear(&mut self, module: usize) -> Result<(), Error> {
+        self.device.clear(module)
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        self.device.flush()
+    }
+
+    fn module_count(&self) -> Option<usize> {
+        self.device.module_count()
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::*;
+    use crate::device::MemoryDevice;
+    use crate::{LED_SQUARE_MATRIX_DIM, MAX_DISPLAYS};
+
+    #[test]
+    fn test_invalid_display_count() {
+        assert!(matches!(
+            Display::new(MemoryDevice::new(1), 0),
+            Err(Error::InvalidDisplayCount(0))
+        ));
+        assert!(matches!(
+            Display::new(MemoryDevice::new(1), MAX_DISPLAYS + 1),
+            Err(Error::InvalidDisplayCount(count)) if count == MAX_DISPLAYS + 1
+        ));
+        assert!(matches!(
+            Display::new(MemoryDevice::new(4), 8),
+            Err(Error::InvalidDisplayCount(8))
+        ));
+        assert!(matches!(
+            Display::new(MemoryDevice::new(4), 2),
+            Err(Error::InvalidDisplayCount(2))
+        ));
+    }
+
+    #[test]
+    fn test_display_uses_display_count() {
+        let mut display = Display::new(MemoryDevice::new(MAX_DISPLAYS), MAX_DISPLAYS).unwrap();
+        display.prepare(0x0A).unwrap();
+        let mut frame = Frame::new(MAX_DISPLAYS);
+        frame.set_intensity(3);
+        frame.data[MAX_DISPLAYS - 1] = [0xFF; 8];
+        display.write_frame(&frame).unwrap();
+        let device = display.into_inner();
+        assert_eq!(device.modules[MAX_DISPLAYS - 1].rows, [0xFF; 8]);
+        assert!(device.modules.iter().all(|module| module.intensity == 3));
+    }
+
+    #[test]
+    fn test_shutdown_on_drop() {
+        let mut device = MemoryDevice::new(2);
+        {
+            let mut display = Display::new(&mut device, 2).unwrap().shutdown_on_drop();
+            display.prepare(0x0F).unwrap();
+            let mut frame = Frame::new(2);
+            frame.data[1] = [0xFF; 8];
+            display.write_frame(&frame).unwrap();
+        }
+        assert!(!device.power);
+        assert_eq!(device.rows(), [[0; 8], [0; 8]]);
+
+        let display = Display::new(&mut device, 2).unwrap().shutdown_on_drop();
+        display.into_inner().prepare(0x0F).unwrap();
+        assert!(device.power);
+    }
+
+    #[test]
+    fn test_with_refresh_keeps_prepared_state() {
+        // like `DisplayConfig::build()`
+        let mut display = Display::new(MemoryDevice::new(2), 2).unwrap();
+        display.prepare(0x07).unwrap();
+        display.set_scan_limit(5).unwrap();
+        let mut display = display.with_refresh(RefreshInterval::Frames(1)).unwrap();
+        let expected = display.device().device().clone();
+
+        // noise on the cable
+        let memory = display.device_mut().device_mut();
+        memory.power = false;
+        memory.modules[0].decode_mode = 0xFF;
+        memory.modules[1].intensity = 0;
+        display.device_mut().flush().unwrap();
+        assert_eq!(display.device().device(), &expected);
+    }
+
+    #[test]
+    fn test_with_refresh_keeps_state_of_frames() {
+        let mut display = Display::new(MemoryDevice::new(2), 2).unwrap();
+        display.prepare(0x0F).unwrap();
+        let mut frame = Frame::new(2);
+        frame.set_intensity(0x03);
+        frame.power = Some(false);
+        display.write_frame(&frame).unwrap();
+        let mut display = display.with_refresh(RefreshInterval::Frames(1)).unwrap();
+
+        let memory = display.device_mut().device_mut();
+        memory.power = true;
+        memory.modules[1].intensity = 0x0F;
+        display.device_mut().flush().unwrap();
+        assert!(!display.device().device().power);
+        assert_eq!(display.device().device().modules[1].intensity, 0x03);
+
+        // only some modules got a new intensity, so it isn't known for all of them
+        let mut display = Display::new(MemoryDevice::new(2), 2).unwrap();
+        display.prepare(0x0F).unwrap();
+        let mut frame = Frame::new(2);
+        frame.intensity[1] = Some(0x03);
+        display.write_frame(&frame).unwrap();
+        let mut display = display.with_refresh(RefreshInterval::Frames(1)).unwrap();
+        display.device_mut().device_mut().modules[0].intensity = 0;
+        display.device_mut().flush().unwrap();
+        assert_eq!(display.device().device().modules[0].intensity, 0);
+    }
+
+    #[test]
+    fn test_orientation() {
+        // top left pixel and the pixel right of it
+        let data = [0b1100_0000, 0, 0, 0, 0, 0, 0, 0];
+        assert_eq!(Orientation::Normal.apply(&data), data);
+        assert_eq!(
+            Orientation::Rotate90.apply(&data),
+            [0b0000_0001, 0b0000_0001, 0, 0, 0, 0, 0, 0]
+        );
+        assert_eq!(
+            Orientation::Rotate180.apply(&data),
+            [0, 0, 0, 0, 0, 0, 0, 0b0000_0011]
+        );
+        assert_eq!(
+            Orientation::Rotate270.apply(&data),
+            [0, 0, 0, 0, 0, 0, 0b1000_0000, 0b1000_0000]
+        );
+
+        let mut display = Display::new(MemoryDevice::new(1), 1).unwrap();
+        display.set_orientation(Orientation::Rotate180);
+        let mut frame = Frame::new(1);
+        frame.data[0] = data;
+        display.write_frame(&frame).unwrap();
+        assert_eq!(
+            display.device().modules[0].rows[LED_SQUARE_MATRIX_DIM - 1],
+            0b11
+        );
+    }
+}
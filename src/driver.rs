@@ -0,0 +1,585 @@
+//! Register-level driver for a chain of MAX7219. Unlike the driver of the "max7219"-crate, it
+//! gives access to all registers, like the scan limit. The bytes are sent over a [`Bus`]: either
+//! bit-banged over GPIO pins ([`PinBus`]) or via SPI ([`SpiBus`]).
+//!
+//! The data for module 0 is sent first, the same as with the "max7219"-crate.
+//!
+//! Several chains can share the data and clock lines if each chain has its own CS (LOAD) line:
+//! the modules of the other chains shift the data through, but only latch it on the rising
+//! edge of their own CS line. Use `SharedPin` for the shared lines.
+
+use crate::device::MatrixDevice;
+use crate::mappings::SingleDisplayData;
+use crate::{check_display_count, Error, LED_SQUARE_MATRIX_DIM, MAX_DISPLAYS};
+#[cfg(feature = "alloc")]
+use alloc::rc::Rc;
+#[cfg(feature = "alloc")]
+use core::cell::RefCell;
+use embedded_hal::blocking::delay::DelayUs;
+use embedded_hal::blocking::spi::Write;
+use embedded_hal::digital::v2::OutputPin;
+use max7219::{DataError, DecodeMode};
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+
+/// A register of the MAX7219.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Register {
+    /// Digit register `0` to `7`, i.e. a row of the matrix.
+    Digit(u8),
+    /// The decode mode register.
+    DecodeMode,
+    /// The intensity register.
+    Intensity,
+    /// The scan limit register.
+    ScanLimit,
+    /// The shutdown register. `0` is shutdown mode, `1` is normal operation.
+    Shutdown,
+    /// The display test register.
+    DisplayTest,
+}
+
+impl Register {
+    /// The address of the register.
+    pub const fn address(self) -> u8 {
+        match self {
+            Self::Digit(digit) => digit + 1,
+            Self::DecodeMode => 0x09,
+            Self::Intensity => 0x0A,
+            Self::ScanLimit => 0x0B,
+            Self::Shutdown => 0x0C,
+            Self::DisplayTest => 0x0F,
+        }
+    }
+}
+
+/// The bytes that write a register of a chain in one transfer, two bytes (address and value) per
+/// module. Shared by [`Driver`] and the async driver, so that both send the same bytes.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Transfer {
+    buffer: [u8; MAX_DISPLAYS * 2],
+    display_count: usize,
+}
+
+impl Transfer {
+    /// Writes the register of a single module of a chain with `display_count` modules. The
+    /// other modules get a no-op.
+    pub(crate) const fn single(
+        display_count: usize,
+        module: usize,
+        register: Register,
+        value: u8,
+    ) -> Result<Self, Error> {
+        if module >= display_count {
+            return Err(Error::InvalidModule(module));
+        }
+        let mut buffer = [0; MAX_DISPLAYS * 2];
+        buffer[module * 2] = register.address();
+        buffer[module * 2 + 1] = value;
+        Ok(Self {
+            buffer,
+            display_count,
+        })
+    }
+
+    /// Writes the register of all modules of a chain with `display_count` modules.
+    pub(crate) fn all(display_count: usize, register: Register, value: u8) -> Self {
+        let mut buffer = [0; MAX_DISPLAYS * 2];
+        for module in 0..display_count {
+            buffer[module * 2] = register.address();
+            buffer[module * 2 + 1] = value;
+        }
+        Self {
+            buffer,
+            display_count,
+        }
+    }
+
+    /// The bytes to send.
+    pub(crate) fn bytes(&self) -> &[u8] {
+        &self.buffer[..self.display_count * 2]
+    }
+}
+
+/// Fails if `limit` is not a valid scan limit.
+pub(crate) const fn check_scan_limit(limit: u8) -> Result<(), Error> {
+    if limit > 7 {
+        Err(Error::InvalidConfig("the scan limit must be at most 7"))
+    } else {
+        Ok(())
+    }
+}
+
+/// Sends the bytes for the whole display chain in one transfer, i.e. with CS (LOAD) low during
+/// the transfer.
+pub trait Bus {
+    /// Sends `data` in one transfer.
+    fn write(&mut self, data: &[u8]) -> Result<(), Error>;
+}
+
+impl<B: Bus + ?Sized> Bus for &mut B {
+    fn write(&mut self, data: &[u8]) -> Result<(), Error> {
+        (**self).write(data)
+    }
+}
+
+/// A delay that doesn't wait. The default of [`PinBus`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoDelay;
+
+impl DelayUs<u32> for NoDelay {
+    fn delay_us(&mut self, _us: u32) {}
+}
+
+/// A delay that busy-waits. Unlike `std::thread::sleep`, it also waits precisely for a few
+/// microseconds, as needed for the clock of a [`PinBus`].
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SpinDelay;
+
+#[cfg(feature = "std")]
+impl DelayUs<u32> for SpinDelay {
+    fn delay_us(&mut self, us: u32) {
+        let start = Instant::now();
+        let duration = Duration::from_micros(u64::from(us));
+        while start.elapsed() < duration {
+            core::hint::spin_loop();
+        }
+    }
+}
+
+/// Bit-bangs SPI over three GPIO pins. By default, the pins are toggled as fast as possible. On
+/// fast SoCs, like the Raspberry Pi 4 or 5, that can be too fast for long wires or level
+/// shifters; then set a minimum clock half-period with [`PinBus::with_delay`].
+#[derive(Debug)]
+pub struct PinBus<DATA, CS, CLK, DELAY = NoDelay> {
+    data: DATA,
+    cs: CS,
+    clk: CLK,
+    delay: DELAY,
+    /// Minimum time the clock is high or low.
+    half_period_us: u32,
+}
+
+impl<DATA, CS, CLK> PinBus<DATA, CS, CLK> {
+    /// Creates the bus from the pins, which must be configured as outputs.
+    ///
+    /// * `data` - the data pin (DIN)
+    /// * `cs` - the cs (chip select) pin, also known as LOAD
+    /// * `clk` - the clock pin
+    pub const fn new(data: DATA, cs: CS, clk: CLK) -> Self {
+        Self::with_delay(data, cs, clk, NoDelay, 0)
+    }
+}
+
+impl<DATA, CS, CLK, DELAY> PinBus<DATA, CS, CLK, DELAY> {
+    /// Like [`PinBus::new`] but the clock is high and low for at least `half_period_us` each,
+    /// i.e. the clock frequency is at most `1 / (2 * half_period_us)`.
+    ///
+    /// * `delay` - delay provider, e.g. from the HAL of the microcontroller or `SpinDelay`
+    /// * `half_period_us` - minimum clock half-period in microseconds; `0` doesn't wait
+    pub const fn with_delay(
+        data: DATA,
+        cs: CS,
+        clk: CLK,
+        delay: DELAY,
+        half_period_us: u32,
+    ) -> Self {
+        Self {
+            data,
+            cs,
+            clk,
+            delay,
+            half_period_us,
+        }
+    }
+
+    /// The minimum clock half-period in microseconds.
+    pub const fn half_period_us(&self) -> u32 {
+        self.half_period_us
+    }
+
+    /// Sets the minimum clock half-period in microseconds; `0` doesn't wait.
+    pub fn set_half_period_us(&mut self, half_period_us: u32) {
+        self.half_period_us = half_period_us;
+    }
+
+    /// Returns the pins.
+    pub fn into_inner(self) -> (DATA, CS, CLK) {
+        (self.data, self.cs, self.clk)
+    }
+}
+
+impl<DATA, CS, CLK, DELAY: DelayUs<u32>> PinBus<DATA, CS, CLK, DELAY> {
+    fn wait(&mut self) {
+        if self.half_period_us > 0 {
+            self.delay.delay_us(self.half_period_us);
+        }
+    }
+}
+
+/// The errors of the pins are passed on as [`Error`], so they must convert into it, e.g.
+/// [`core::convert::Infallible`] or, with `std`, `gpio_cdev::Error` as `Error::Gpio`.
+impl<DATA, CS, CLK, DELAY> Bus for PinBus<DATA, CS, CLK, DELAY>
+where
+    DATA: OutputPin,
+    DATA::Error: Into<Error>,
+    CS: OutputPin,
+    CS::Error: Into<Error>,
+    CLK: OutputPin,
+    CLK::Error: Into<Error>,
+    DELAY: DelayUs<u32>,
+{
+    fn write(&mut self, data: &[u8]) -> Result<(), Error> {
+        self.cs.set_low().map_err(Into::into)?;
+        for byte in data {
+            for i in 0..8 {
+                if byte & (0b1000_0000 >> i) != 0 {
+                    self.data.set_high().map_err(Into::into)?;
+                } else {
+                    self.data.set_low().map_err(Into::into)?;
+                }
+                self.wait();
+                self.clk.set_high().map_err(Into::into)?;
+                self.wait();
+                self.clk.set_low().map_err(Into::into)?;
+            }
+        }
+        self.wait();
+        self.cs.set_high().map_err(Into::into)?;
+        Ok(())
+    }
+}
+
+/// An output pin that is used by several buses, e.g. the data and clock lines of chains that
+/// only have separate CS lines. All clones refer to the same pin. The buses must be used from
+/// the same thread.
+#[cfg(feature = "alloc")]
+#[derive(Debug)]
+pub struct SharedPin<P>(Rc<RefCell<P>>);
+
+#[cfg(feature = "alloc")]
+impl<P> SharedPin<P> {
+    /// Shares `pin`; clone it for each bus.
+    pub fn new(pin: P) -> Self {
+        Self(Rc::new(RefCell::new(pin)))
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<P> Clone for SharedPin<P> {
+    fn clone(&self) -> Self {
+        Self(Rc::clone(&self.0))
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<P: OutputPin> OutputPin for SharedPin<P> {
+    type Error = P::Error;
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.0.borrow_mut().set_low()
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.0.borrow_mut().set_high()
+    }
+}
+
+/// Uses an SPI device that controls the CS (LOAD) line itself. The SPI device must use mode 0
+/// with at most 10 MHz.
+#[derive(Debug)]
+pub struct SpiBus<SPI>(pub SPI);
+
+impl<SPI: Write<u8>> Bus for SpiBus<SPI> {
+    fn write(&mut self, data: &[u8]) -> Result<(), Error> {
+        self.0.write(data).map_err(|_| DataError::Spi)?;
+        Ok(())
+    }
+}
+
+/// Driver for a chain of MAX7219.
+#[derive(Debug)]
+pub struct Driver<B> {
+    bus: B,
+    display_count: usize,
+    /// The scan limit of each module. Rows after it are not sent.
+    scan_limits: [u8; MAX_DISPLAYS],
+}
+
+impl<B: Bus> Driver<B> {
+    /// Creates the driver for `display_count` chained displays and brings all modules into the
+    /// same state as the initialization of the "max7219"-crate: test mode off, all rows
+    /// enabled, no decode mode, cleared and powered off.
+    pub fn new(bus: B, display_count: usize) -> Result<Self, Error> {
+        check_display_count(display_count)?;
+        let mut driver = Self {
+            bus,
+            display_count,
+            scan_limits: [7; MAX_DISPLAYS],
+        };
+        driver.write_register_all(Register::DisplayTest, 0)?;
+        driver.write_register_all(Register::ScanLimit, 7)?;
+        driver.write_register_all(Register::DecodeMode, DecodeMode::NoDecode as u8)?;
+        for digit in 0..LED_SQUARE_MATRIX_DIM as u8 {
+            driver.write_register_all(Register::Digit(digit), 0)?;
+        }
+        driver.write_register_all(Register::Shutdown, 0)?;
+        Ok(driver)
+    }
+
+    /// Count of displays connected to the MAX7219.
+    pub fn display_count(&self) -> usize {
+        self.display_count
+    }
+
+    /// The bus, e.g. to change the clock of a [`PinBus`].
+    pub fn bus_mut(&mut self) -> &mut B {
+        &mut self.bus
+    }
+
+    /// Returns the bus.
+    pub fn into_inner(self) -> B {
+        self.bus
+    }
+
+    /// Writes the register of a single module. The other modules get a no-op.
+    pub fn write_register(
+        &mut self,
+        module: usize,
+        register: Register,
+        value: u8,
+    ) -> Result<(), Error> {
+        let transfer = Transfer::single(self.display_count, module, register, value)?;
+        self.bus.write(transfer.bytes())
+    }
+
+    /// Writes the register of all modules in one transfer.
+    pub fn write_register_all(&mut self, register: Register, value: u8) -> Result<(), Error> {
+        let transfer = Transfer::all(self.display_count, register, value);
+        self.bus.write(transfer.bytes())
+    }
+}
+
+impl<B: Bus> MatrixDevice for Driver<B> {
+    /// Only writes the rows up to the scan limit of the module; the other rows are not shown.
+    fn write_rows(&mut self, module: usize, rows: &SingleDisplayData) -> Result<(), Error> {
+        if module >= self.display_count {
+            return Err(Error::InvalidModule(module));
+        }
+        let shown = usize::from(self.scan_limits[module]) + 1;
+        for (digit, row) in rows.iter().enumerate().take(shown) {
+            self.write_register(module, Register::Digit(digit as u8), *row)?;
+        }
+        Ok(())
+    }
+
+    fn set_intensity(&mut self, module: usize, intensity: u8) -> Result<(), Error> {
+        self.write_register(module, Register::Intensity, intensity)
+    }
+
+    fn power_on(&mut self) -> Result<(), Error> {
+        self.write_register_all(Register::Shutdown, 1)
+    }
+
+    fn power_off(&mut self) -> Result<(), Error> {
+        self.write_register_all(Register::Shutdown, 0)
+    }
+
+    fn set_scan_limit(&mut self, module: usize, limit: u8) -> Result<(), Error> {
+        check_scan_limit(limit)?;
+        self.write_register(module, Register::ScanLimit, limit)?;
+        self.scan_limits[module] = limit;
+        Ok(())
+    }
+
+    fn set_decode_mode(&mut self, module: usize, mode: DecodeMode) -> Result<(), Error> {
+        self.write_register(module, Register::DecodeMode, mode as u8)
+    }
+
+    fn set_display_test(&mut self, module: usize, on: bool) -> Result<(), Error> {
+        self.write_register(module, Register::DisplayTest, u8::from(on))
+    }
+
+    fn module_count(&self) -> Option<usize> {
+        Some(self.display_count)
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+    use core::convert::Infallible;
+
+    /// Records the data of each transfer.
+    #[derive(Default)]
+    struct RecordingBus(Vec<Vec<u8>>);
+
+    impl Bus for RecordingBus {
+        fn write(&mut self, data: &[u8]) -> Result<(), Error> {
+            self.0.push(data.to_vec());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_init_and_register_writes() {
+        let mut driver = Driver::new(RecordingBus::default(), 2).unwrap();
+        // test mode, scan limit, decode mode, 8 digits and shutdown
+        assert_eq!(driver.bus.0.len(), 12);
+        assert_eq!(driver.bus.0[1], [0x0B, 7, 0x0B, 7]);
+        driver.bus.0.clear();
+
+        driver.set_intensity(1, 0x0A).unwrap();
+        assert!(matches!(
+            driver.set_intensity(2, 0),
+            Err(Error::InvalidModule(2))
+        ));
+        assert_eq!(driver.into_inner().0, [[0, 0, 0x0A, 0x0A]]);
+    }
+
+    /// Records the levels of all pins with their names.
+    #[derive(Clone)]
+    struct LogPin(char, Rc<RefCell<Vec<(char, bool)>>>);
+
+    impl OutputPin for LogPin {
+        type Error = Infallible;
+
+        fn set_low(&mut self) -> Result<(), Infallible> {
+            self.1.borrow_mut().push((self.0, false));
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), Infallible> {
+            self.1.borrow_mut().push((self.0, true));
+            Ok(())
+        }
+    }
+
+    /// The bytes latched by the chain with the given CS pin.
+    fn latched(log: &[(char, bool)], cs: char) -> Vec<Vec<u8>> {
+        let mut transfers = Vec::new();
+        let (mut selected, mut data, mut bits) = (false, false, Vec::new());
+        for &(pin, level) in log {
+            match pin {
+                'd' => data = level,
+                'c' if level && selected => bits.push(data),
+                pin if pin == cs => {
+                    selected = !level;
+                    if level && !bits.is_empty() {
+                        let bytes = bits
+                            .chunks(8)
+                            .map(|byte| byte.iter().fold(0, |acc, bit| acc << 1 | u8::from(*bit)));
+                        transfers.push(bytes.collect());
+                        bits.clear();
+                    }
+                }
+                _ => {}
+            }
+        }
+        transfers
+    }
+
+    #[test]
+    fn test_chains_share_data_and_clock() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let data = SharedPin::new(LogPin('d', log.clone()));
+        let clk = SharedPin::new(LogPin('c', log.clone()));
+        let bus_a = PinBus::new(data.clone(), LogPin('a', log.clone()), clk.clone());
+        let bus_b = PinBus::new(data, LogPin('b', log.clone()), clk);
+        let mut chain_a = Driver::new(bus_a, 1).unwrap();
+        let mut chain_b = Driver::new(bus_b, 2).unwrap();
+        log.borrow_mut().clear();
+
+        chain_a.set_intensity(0, 0x03).unwrap();
+        chain_b.set_intensity(1, 0x0C).unwrap();
+        assert_eq!(latched(&log.borrow(), 'a'), [[0x0A, 0x03]]);
+        assert_eq!(latched(&log.borrow(), 'b'), [[0, 0, 0x0A, 0x0C]]);
+    }
+
+    /// Counts the waited microseconds.
+    struct CountingDelay(u32);
+
+    impl DelayUs<u32> for CountingDelay {
+        fn delay_us(&mut self, us: u32) {
+            self.0 += us;
+        }
+    }
+
+    #[test]
+    fn test_clock_half_period() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let pin = |name| LogPin(name, log.clone());
+        let mut bus = PinBus::with_delay(pin('d'), pin('a'), pin('c'), CountingDelay(0), 2);
+        bus.write(&[0x0A, 0x03]).unwrap();
+        // high and low for each of the 16 bits and before cs goes high
+        assert_eq!(bus.delay.0, 16 * 2 * 2 + 2);
+        assert_eq!(latched(&log.borrow(), 'a'), [[0x0A, 0x03]]);
+
+        bus.set_half_period_us(0);
+        bus.write(&[0]).unwrap();
+        assert_eq!(bus.delay.0, 16 * 2 * 2 + 2);
+    }
+
+    #[test]
+    fn test_write_rows_respects_scan_limit() {
+        let mut driver = Driver::new(RecordingBus::default(), 1).unwrap();
+        driver.set_scan_limit(0, 3).unwrap();
+        driver.bus.0.clear();
+        driver.write_rows(0, &[0xFF; 8]).unwrap();
+        assert_eq!(
+            driver.into_inner().0,
+            [[0x01, 0xFF], [0x02, 0xFF], [0x03, 0xFF], [0x04, 0xFF]]
+        );
+    }
+
+    #[test]
+    fn test_write_rows_rejects_invalid_modules() {
+        let mut driver = Driver::new(RecordingBus::default(), 2).unwrap();
+        driver.bus.0.clear();
+        for module in [2, MAX_DISPLAYS, MAX_DISPLAYS + 1] {
+            assert!(matches!(
+                driver.write_rows(module, &[0xFF; 8]),
+                Err(Error::InvalidModule(invalid)) if invalid == module
+            ));
+        }
+        assert!(driver.into_inner().0.is_empty());
+    }
+
+    #[test]
+    fn test_display_count_must_match_driver() {
+        let driver = Driver::new(RecordingBus::default(), 4).unwrap();
+        assert!(matches!(
+            crate::Display::new(driver, 8),
+            Err(Error::InvalidDisplayCount(8))
+        ));
+    }
+
+    /// A pin that fails, like a GPIO line that was released.
+    #[cfg(feature = "std")]
+    struct FailingPin;
+
+    #[cfg(feature = "std")]
+    impl OutputPin for FailingPin {
+        type Error = gpio_cdev::Error;
+
+        fn set_low(&mut self) -> Result<(), Self::Error> {
+            Err(std::io::Error::from(std::io::ErrorKind::BrokenPipe).into())
+        }
+
+        fn set_high(&mut self) -> Result<(), Self::Error> {
+            self.set_low()
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_pin_error_is_passed_on() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut bus = PinBus::new(LogPin('d', log.clone()), FailingPin, LogPin('c', log));
+        let error = bus.write(&[0x0A, 0x03]).unwrap_err();
+        assert!(matches!(error, Error::Gpio(_)));
+        assert!(std::error::Error::source(&error).is_some());
+    }
+}
@@ -0,0 +1,194 @@
+//! Attention effects for urgent messages, like blinking, flashing and inverting.
+//! All effects are [`Animation`]s and can be chained with other animations.
+
+use crate::animation::{set_pixel, Animation, Frame};
+use crate::mappings::SingleDisplayData;
+use crate::LED_SQUARE_MATRIX_DIM;
+use alloc::vec::Vec;
+
+/// A rectangular region of pixels on the display chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    /// First col of the region. Col 0 is the leftmost col of the first display.
+    pub x: usize,
+    /// First row of the region.
+    pub y: usize,
+    /// Width in cols.
+    pub width: usize,
+    /// Height in rows.
+    pub height: usize,
+}
+
+impl Region {
+    /// Creates a new region.
+    pub const fn new(x: usize, y: usize, width: usize, height: usize) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Region that covers the single display with the given index.
+    pub const fn display(index: usize) -> Self {
+        Self::new(
+            index * LED_SQUARE_MATRIX_DIM,
+            0,
+            LED_SQUARE_MATRIX_DIM,
+            LED_SQUARE_MATRIX_DIM,
+        )
+    }
+
+    /// Turns all pixels of the region off. Pixels outside of `data` are ignored.
+    pub fn clear(&self, data: &mut [SingleDisplayData]) {
+        let width = data.len() * LED_SQUARE_MATRIX_DIM;
+        for x in self.x..(self.x + self.width).min(width) {
+            for y in self.y..(self.y + self.height).min(LED_SQUARE_MATRIX_DIM) {
+                set_pixel(data, x, y, false);
+            }
+        }
+    }
+}
+
+/// Inverts all pixels: lit pixels are turned off and vice versa. This way the content is shown
+/// as dark pixels on a lit background.
+pub fn invert(data: &mut [SingleDisplayData]) {
+    data.iter_mut()
+        .flat_map(|display| display.iter_mut())
+        .for_each(|row| *row = !*row);
+}
+
+/// Blinks the content of the display. If no [`Region`] is set, the whole chain is blinked by
+/// powering the displays off and on, which is cheaper than redrawing. Otherwise, only the pixels
+/// of the region are turned off and on.
+///
+/// The content is either set via [`Self::content`] or the content of the frame at the first step,
+/// i.e. the last frame of the previous animation. After the last blink, the content stays visible.
+#[derive(Debug, Clone)]
+pub struct Blink {
+    period: usize,
+    times: Option<usize>,
+    region: Option<Region>,
+    content: Option<Vec<SingleDisplayData>>,
+    step: usize,
+}
+
+impl Blink {
+    /// Creates an endless blink effect.
+    ///
+    /// * `period` - frames of one off/on cycle. The content is off in the first half.
+    pub const fn new(period: usize) -> Self {
+        Self {
+            period,
+            times: None,
+            region: None,
+            content: None,
+            step: 0,
+        }
+    }
+
+    /// Creates an effect that flashes the content `times` times before it settles.
+    ///
+    /// * `times` - count of flashes
+    /// * `period` - frames of one off/on cycle
+    pub const fn flash(times: usize, period: usize) -> Self {
+        Self::new(period).times(times)
+    }
+
+    /// Stops after `times` off/on cycles.
+    pub const fn times(mut self, times: usize) -> Self {
+        self.times = Some(times);
+        self
+    }
+
+    /// Only blinks the pixels in `region`.
+    pub const fn region(mut self, region: Region) -> Self {
+        self.region = Some(region);
+        self
+    }
+
+    /// Sets the content to blink.
+    pub fn content(mut self, content: Vec<SingleDisplayData>) -> Self {
+        self.content = Some(content);
+        self
+    }
+}
+
+impl Animation for Blink {
+    fn next_frame(&mut self, frame: &mut Frame) -> bool {
+        if self.period == 0
+            || self
+                .times
+                .map_or(false, |times| self.step >= times * self.period)
+        {
+            return false;
+        }
+        let content = self.content.get_or_insert_with(|| frame.data.clone());
+        let off = self.step % self.period < self.period / 2;
+        self.step += 1;
+
+        frame.set_data(content);
+        match self.region {
+            None => frame.power = Some(!off),
+            Some(region) => {
+                frame.power = Some(true);
+                if off {
+                    region.clear(&mut frame.data);
+                }
+            }
+        }
+        true
+    }
+}
+
+/// Shows the frames of another animation inverted. See [`invert`].
+#[derive(Debug)]
+pub struct Inverted<A>(pub A);
+
+impl<A: Animation> Animation for Inverted<A> {
+    fn next_frame(&mut self, frame: &mut Frame) -> bool {
+        // the wrapped animation may rely on the content of the last frame
+        invert(&mut frame.data);
+        let has_next = self.0.next_frame(frame);
+        invert(&mut frame.data);
+        has_next
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flash_powers_off_and_on() {
+        let mut blink = Blink::flash(2, 4).content(vec![[0xFF; 8]]);
+        let mut frame = Frame::new(1);
+        let mut power = Vec::new();
+        while blink.next_frame(&mut frame) {
+            assert_eq!(frame.data, [[0xFF; 8]]);
+            power.push(frame.power.unwrap());
+        }
+        assert_eq!(power, [false, false, true, true, false, false, true, true]);
+    }
+
+    #[test]
+    fn test_blink_region() {
+        let mut blink = Blink::new(2)
+            .region(Region::display(1))
+            .content(vec![[0xFF; 8], [0xFF; 8]]);
+        let mut frame = Frame::new(2);
+        assert!(blink.next_frame(&mut frame));
+        assert_eq!(frame.data, [[0xFF; 8], [0; 8]]);
+        assert_eq!(frame.power, Some(true));
+        assert!(blink.next_frame(&mut frame));
+        assert_eq!(frame.data, [[0xFF; 8], [0xFF; 8]]);
+    }
+
+    #[test]
+    fn test_invert() {
+        let mut data = vec![[0b1010_0000; 8]];
+        invert(&mut data);
+        assert_eq!(data, [[0b0101_1111; 8]]);
+    }
+}
@@ -2,6 +2,8 @@
 //! MAX7219-powered LED matrix.
 
 use crate::mappings::*;
+use crate::{transpose_single_display_data, Error, LED_SQUARE_MATRIX_DIM};
+#[cfg(feature = "alloc")]
 use alloc::vec::Vec;
 
 /// Encodes a char to its bit-representation on a single display. This means a 8x8 bit matrix.
@@ -41,6 +43,14 @@ pub const fn encode_char(c: char) -> SingleDisplayData {
 
         '0' => ZERO,
         '1' => ONE,
+        '2' => TWO,
+        '3' => THREE,
+        '4' => FOUR,
+        '5' => FIVE,
+        '6' => SIX,
+        '7' => SEVEN,
+        '8' => EIGHT,
+        '9' => NINE,
 
         '.' => DOT,
         '!' => EXCLAMATION_MARK,
@@ -55,6 +65,116 @@ pub const fn encode_char(c: char) -> SingleDisplayData {
 /// So if you have 4 displays and 10 chars then you could only display indices zero to three and
 /// bit shift all rows one to the left per iteration. This way you get a smooth transition/animation.
 /// Don't forget about the sleep-timeout per iteration!
+#[cfg(feature = "alloc")]
 pub fn encode_string(s: &str) -> Vec<SingleDisplayData> {
-    s.chars().into_iter().map(encode_char).collect()
+    s.chars().map(encode_char).collect()
+}
+
+/// Like `encode_string()` but writes the encoded chars into `buffer` and doesn't need a heap.
+/// Returns the count of encoded chars. Fails with [`Error::TextTooLong`] if `buffer` is too
+/// small.
+pub fn encode_string_into(s: &str, buffer: &mut [SingleDisplayData]) -> Result<usize, Error> {
+    let len = s.chars().count();
+    if len > buffer.len() {
+        return Err(Error::TextTooLong(len));
+    }
+    for (display_data, c) in buffer.iter_mut().zip(s.chars()) {
+        *display_data = encode_char(c);
+    }
+    Ok(len)
+}
+
+/// Iterator over the cols of a text, from the left to the right. The characters are encoded
+/// one at a time when their cols are needed, so the memory use doesn't grow with the length of
+/// the text. This is useful for long texts, e.g. a news ticker on a microcontroller. In a col,
+/// the most significant bit is the top row, like in the result of
+/// [`transpose_single_display_data`].
+///
+/// ```
+/// # use max_7219_led_matrix_util::encoding::TextColumns;
+/// let cols = TextColumns::new("HELLO WORLD".chars(), Some(1));
+/// assert!(cols.clone().count() < 11 * 8);
+/// ```
+#[derive(Debug, Clone)]
+pub struct TextColumns<I> {
+    chars: I,
+    /// The cols of the current character.
+    glyph: SingleDisplayData,
+    /// Index of the next col of `glyph`.
+    x: usize,
+    gap_width: Option<usize>,
+    /// Count of empty cols since the last col that was not empty.
+    empty_cols: usize,
+}
+
+impl<I> TextColumns<I> {
+    /// Creates the iterator for the given characters.
+    ///
+    /// * `chars` - the characters of the text, e.g. `"HELLO".chars()`
+    /// * `gap_width` - (optional) set's the maximum width/count of empty cols between characters.
+    ///                 Unlike `remove_gaps_in_display_text()`, this also shortens the empty cols
+    ///                 at the begin and the end. Downside is that spaces will be removed.
+    pub const fn new(chars: I, gap_width: Option<usize>) -> Self {
+        Self {
+            chars,
+            glyph: [0; LED_SQUARE_MATRIX_DIM],
+            x: LED_SQUARE_MATRIX_DIM,
+            gap_width,
+            empty_cols: 0,
+        }
+    }
+}
+
+impl<I: Iterator<Item = char>> Iterator for TextColumns<I> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        loop {
+            if self.x == LED_SQUARE_MATRIX_DIM {
+                self.glyph = transpose_single_display_data(&encode_char(self.chars.next()?));
+                self.x = 0;
+            }
+            let col = self.glyph[self.x];
+            self.x += 1;
+
+            if col != 0 {
+                self.empty_cols = 0;
+                return Some(col);
+            }
+            self.empty_cols += 1;
+            if self
+                .gap_width
+                .map_or(true, |gap_width| self.empty_cols <= gap_width)
+            {
+                return Some(col);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn test_text_columns() {
+        let cols: Vec<u8> = TextColumns::new("HI".chars(), None).collect();
+        let mut expected = Vec::new();
+        expected.extend(transpose_single_display_data(&CAP_H));
+        expected.extend(transpose_single_display_data(&CAP_I));
+        assert_eq!(cols, expected);
+        assert_eq!(TextColumns::new("".chars(), Some(1)).next(), None);
+    }
+
+    #[test]
+    fn test_text_columns_limit_gaps() {
+        let cols: Vec<u8> = TextColumns::new("I  I".chars(), Some(2)).collect();
+        let non_empty: Vec<u8> = cols.iter().copied().filter(|col| *col != 0).collect();
+        assert_eq!(non_empty.len(), 2);
+        assert!(cols.windows(3).all(|cols| cols != [0, 0, 0]));
+        // the empty cols between the two characters are shortened to 2
+        let first = cols.iter().position(|col| *col != 0).unwrap();
+        assert_eq!(cols[first + 1..first + 4], [0, 0, non_empty[1]]);
+    }
 }
@@ -0,0 +1,117 @@
+//! The error type of this crate.
+
+use core::convert::Infallible;
+use core::fmt;
+use max7219::DataError;
+
+/// Errors of this crate.
+#[derive(Debug)]
+pub enum Error {
+    /// Opening the GPIO chip failed.
+    #[cfg(feature = "std")]
+    GpioOpen(gpio_cdev::Error),
+    /// There is no GPIO chip with the given label.
+    #[cfg(feature = "std")]
+    ChipNotFound(std::string::String),
+    /// The GPIO chip has no line with the given name.
+    #[cfg(feature = "std")]
+    LineNotFound(std::string::String),
+    /// Requesting the GPIO line with the given offset failed, e.g. because it is already in use.
+    #[cfg(feature = "std")]
+    LineRequest(u32, gpio_cdev::Error),
+    /// An I/O operation failed, e.g. opening the SPI device or writing to the terminal.
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+    /// Setting a GPIO line of a [`crate::driver::PinBus`] failed.
+    #[cfg(feature = "std")]
+    Gpio(gpio_cdev::Error),
+    /// The communication with the MAX7219 failed.
+    Data(DataError),
+    /// The driver doesn't support the operation.
+    Unsupported,
+    /// The module index is not part of the display chain.
+    InvalidModule(usize),
+    /// The display count is `0`, greater than [`crate::MAX_DISPLAYS`] for a single chain or
+    /// differs from the module count of the device.
+    InvalidDisplayCount(usize),
+    /// The character can't be shown, e.g. on a 7-segment display.
+    UnsupportedChar(char),
+    /// The text with the given count of characters doesn't fit on the display.
+    TextTooLong(usize),
+    /// The configuration is invalid.
+    InvalidConfig(&'static str),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "std")]
+            Self::GpioOpen(error) => write!(f, "opening the GPIO chip failed: {}", error),
+            #[cfg(feature = "std")]
+            Self::ChipNotFound(label) => write!(f, "there is no GPIO chip with label {:?}", label),
+            #[cfg(feature = "std")]
+            Self::LineNotFound(name) => write!(f, "the GPIO chip has no line named {:?}", name),
+            #[cfg(feature = "std")]
+            Self::LineRequest(line, error) => {
+                write!(f, "requesting GPIO line {} failed: {}", line, error)
+            }
+            #[cfg(feature = "std")]
+            Self::Io(error) => write!(f, "I/O error: {}", error),
+            #[cfg(feature = "std")]
+            Self::Gpio(error) => write!(f, "setting a GPIO line failed: {}", error),
+            Self::Data(error) => write!(f, "communication with the MAX7219 failed: {:?}", error),
+            Self::Unsupported => write!(f, "the operation is not supported by the driver"),
+            Self::InvalidModule(module) => {
+                write!(f, "module {} is not part of the display chain", module)
+            }
+            Self::InvalidDisplayCount(count) => write!(
+                f,
+                "invalid display count {}; must be between 1 and {} per chain and match the device",
+                count,
+                crate::MAX_DISPLAYS
+            ),
+            Self::UnsupportedChar(c) => write!(f, "the character {:?} can't be shown", c),
+            Self::TextTooLong(count) => {
+                write!(f, "the text with {} characters doesn't fit", count)
+            }
+            Self::InvalidConfig(reason) => write!(f, "invalid configuration: {}", reason),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::GpioOpen(error) | Self::LineRequest(_, error) | Self::Gpio(error) => Some(error),
+            Self::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<DataError> for Error {
+    fn from(error: DataError) -> Self {
+        Self::Data(error)
+    }
+}
+
+impl From<Infallible> for Error {
+    fn from(error: Infallible) -> Self {
+        match error {}
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<gpio_cdev::Error> for Error {
+    fn from(error: gpio_cdev::Error) -> Self {
+        Self::Gpio(error)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
@@ -11,10 +11,13 @@
 // now allow a few rules which are denied by the above statement
 // --> they are ridiculous and not necessary
 #![allow(
+    clippy::doc_overindented_list_items,
     clippy::fallible_impl_from,
+    clippy::multiple_crate_versions,
     clippy::needless_doctest_main,
     clippy::redundant_pub_crate,
-    clippy::suboptimal_flops
+    clippy::suboptimal_flops,
+    clippy::too_long_first_doc_paragraph
 )]
 #![deny(missing_docs)]
 #![deny(missing_debug_implementations)]
@@ -24,34 +27,81 @@
 #[cfg(feature = "std")]
 extern crate std;
 
-#[macro_use]
+#[cfg(any(feature = "alloc", test))]
+#[cfg_attr(feature = "alloc", macro_use)]
 extern crate alloc;
+#[cfg(feature = "alloc")]
 use alloc::vec::Vec;
 
-#[cfg(feature = "std")]
-use crate::setup::Max7219;
-
 #[cfg(feature = "std")]
 use std::{thread::sleep, time::Duration};
 
-#[cfg(feature = "std")]
-use crate::encoding::encode_string;
+#[cfg(feature = "alloc")]
+use crate::animation::{Animation, Frame, Scroller};
+use crate::device::MatrixDevice;
+use crate::encoding::encode_char;
 use crate::mappings::SingleDisplayData;
-#[cfg(feature = "std")]
+use crate::matrix::Matrix;
+use embedded_hal::blocking::delay::DelayMs;
 use max7219::DecodeMode;
 
 /// We use 8x8 square matrices (per single display)
 pub const LED_SQUARE_MATRIX_DIM: usize = 8;
 
-/// Maximum supported chained displays by MAX7219.
-pub const MAX_DISPLAYS: usize = 16;
+/// Maximum supported chained displays by the driver of the "max7219"-crate.
+pub const MAX_DISPLAYS: usize = 8;
 
+#[cfg(feature = "alloc")]
+pub mod animation;
+#[cfg(feature = "async")]
+pub mod asynch;
+pub mod brightness;
+#[cfg(feature = "std")]
+mod config;
+#[cfg(feature = "std")]
+pub use config::DisplayConfig;
+pub mod device;
+mod display;
+pub mod driver;
+pub use display::{Display, Orientation, ShutdownGuard};
+#[cfg(feature = "alloc")]
+pub mod effects;
 pub mod encoding;
+mod error;
+pub use error::Error;
 pub mod mappings;
+pub mod matrix;
+#[cfg(feature = "alloc")]
+pub mod playlist;
+#[cfg(feature = "alloc")]
+pub mod recording;
+#[cfg(feature = "alloc")]
+pub mod refresh;
+#[cfg(feature = "alloc")]
+pub mod self_test;
 #[cfg(feature = "std")]
 mod setup;
+#[cfg(feature = "alloc")]
+pub mod seven_segment;
+#[cfg(feature = "std")]
+pub use setup::{
+    setup as setup_adapter, setup_chains as setup_chain_adapters, GpioChip, GpioLine,
+    Max7219 as Max7219Adapter, Max7219Chain as Max7219ChainAdapter,
+};
+#[cfg(feature = "signal")]
+mod signal;
+#[cfg(feature = "signal")]
+pub use signal::stop_on_signal;
+#[cfg(feature = "spi")]
+mod spi;
+#[cfg(feature = "spi")]
+pub use spi::{setup as setup_spi_adapter, Max7219Spi as Max7219SpiAdapter, MAX_SPEED_HZ};
 #[cfg(feature = "std")]
-pub use setup::{setup as setup_adapter, Max7219 as Max7219Adapter};
+pub mod terminal;
+#[cfg(feature = "alloc")]
+pub mod transition;
+#[cfg(feature = "alloc")]
+pub mod typewriter;
 
 /// Shift all row bits one to the left (to the next col). This way you can animate a moving text.
 ///
@@ -59,6 +109,7 @@ pub use setup::{setup as setup_adapter, Max7219 as Max7219Adapter};
 ///                 the 8x8 bit data for a single display.
 /// * `repeat` shift 1 bits on the very left to the ending of the vector. Without repeat
 //            the vector will be all zeros after enough iterations.
+#[allow(clippy::needless_range_loop)]
 pub fn shift_all_rows_one_bit_left(moving_bits: &mut [SingleDisplayData] /*, repeat: bool*/) {
     // move all bits to next position
 
@@ -87,59 +138,435 @@ pub fn shift_all_rows_one_bit_left(moving_bits: &mut [SingleDisplayData] /*, rep
     }
 }
 
+/// Shifts all rows one bit to the left like [`shift_all_rows_one_bit_left`], but without moving
+/// the leftmost col to the end: `col` becomes the rightmost col of the last display. The most
+/// significant bit of `col` is the top row.
+#[allow(clippy::needless_range_loop)]
+pub(crate) fn shift_in_col(data: &mut [SingleDisplayData], col: u8) {
+    let len = data.len();
+    for display_i in 0..len {
+        for row_i in 0..LED_SQUARE_MATRIX_DIM {
+            let next_bit = if display_i + 1 < len {
+                data[display_i + 1][row_i] >> 7
+            } else {
+                (col >> (7 - row_i)) & 1
+            };
+            data[display_i][row_i] = (data[display_i][row_i] << 1) | next_bit;
+        }
+    }
+}
+
 /// Convenient function that turns on the display, clears the display
 /// and sets the brightness to the highest possible value. It also sets
 /// the DecodeMode to NoDecode which is necessary for displaying content on
-/// the 8x8 matrix display. (Max7219 can also be used for 7 segment displays).
+/// the 8x8 matrix display. (Max7219 can also be used for 7 segment displays, see
+/// `seven_segment`).
+///
+/// Works with every [`MatrixDevice`], e.g. the driver of the "max7219"-crate with every connector
+/// (`setup_adapter()` on Linux or [`max7219::MAX7219::from_spi`] on a microcontroller).
 ///
-/// * `display` - mutable reference to Max7219 display driver
+/// * `display` - mutable reference to the display device, e.g. the Max7219 display driver
 /// * `display_count` - count of displays connected to the MAX7219
 /// * `intensity` - brightness for the display; value between `0x00` and `0x0F`
-#[cfg(feature = "std")]
-pub fn prepare_display(display: &mut Max7219, display_count: usize, intensity: u8) {
-    let display_count = display_count % MAX_DISPLAYS;
+pub fn prepare_display<D: MatrixDevice>(
+    display: &mut D,
+    display_count: usize,
+    intensity: u8,
+) -> Result<(), Error> {
+    check_display_count_of(display.module_count(), display_count)?;
 
-    display.power_on().unwrap();
+    display.power_on()?;
     for i in 0..display_count {
-        display.set_decode_mode(i, DecodeMode::NoDecode).unwrap();
-        display.clear_display(i).unwrap();
-        display.set_intensity(i, intensity).unwrap();
+        display.set_decode_mode(i, DecodeMode::NoDecode)?;
+        display.clear(i)?;
+        display.set_intensity(i, intensity)?;
     }
+    display.flush()
+}
+
+/// Like [`prepare_display`] for the `N` displays of a [`Matrix<N>`](Matrix).
+///
+/// * `display` - mutable reference to the display device, e.g. the Max7219 display driver
+/// * `intensity` - brightness for the display; value between `0x00` and `0x0F`
+pub fn prepare_matrix<D: MatrixDevice, const N: usize>(
+    display: &mut D,
+    intensity: u8,
+) -> Result<(), Error> {
+    prepare_display(display, N, intensity)
 }
 
 /// Shows a moving text in loop. After each iteration all bits are shifted one col to the left.
+/// This only returns if writing to the display fails.
 /// **Make sure to call `prepare_display()` first!**
 ///
-/// * `display` - mutable reference to Max7219 display driver
+/// * `display` - mutable reference to the display device, e.g. the Max7219 display driver
 /// * `text` - the text to display
 /// * `display_count` - count of displays connected to the MAX7219
 /// * `ms_sleep` - timeout after each iteration
 /// * `gap_width` - (optional) set's the maximum width/count of empty cols between characters. 0 to deactivate.
 ///                 Downside is that spaces will be removed.
 #[cfg(feature = "std")]
-pub fn show_moving_text_in_loop(
-    display: &mut Max7219,
+pub fn show_moving_text_in_loop<D: MatrixDevice>(
+    display: &mut D,
     text: &str,
     display_count: usize,
     ms_sleep: u64,
     gap_width: Option<usize>,
-) {
-    let display_count = display_count % MAX_DISPLAYS;
+) -> Result<(), Error> {
+    play_animation(
+        display,
+        Scroller::new(text, gap_width),
+        display_count,
+        ms_sleep,
+    )
+}
 
-    let raw_bits = encode_string(text);
-    let mut display_data_vec: Vec<SingleDisplayData> = if let Some(gap_width) = gap_width {
-        remove_gaps_in_display_text(&raw_bits, gap_width)
-    } else {
-        raw_bits
-    };
+/// Like `show_moving_text_in_loop()` but waits with `delay` after each iteration. This also
+/// works in `no_std` environments.
+///
+/// * `display` - mutable reference to the display device, e.g. the Max7219 display driver
+/// * `text` - the text to display
+/// * `display_count` - count of displays connected to the MAX7219
+/// * `delay` - delay provider, e.g. from the HAL of the microcontroller
+/// * `ms_sleep` - timeout after each iteration
+/// * `gap_width` - (optional) set's the maximum width/count of empty cols between characters.
+///                 Downside is that spaces will be removed.
+#[cfg(feature = "alloc")]
+pub fn show_moving_text_in_loop_with_delay<D: MatrixDevice, T: DelayMs<u32>>(
+    display: &mut D,
+    text: &str,
+    display_count: usize,
+    delay: &mut T,
+    ms_sleep: u32,
+    gap_width: Option<usize>,
+) -> Result<(), Error> {
+    play_animation_with_delay(
+        display,
+        Scroller::new(text, gap_width),
+        display_count,
+        delay,
+        ms_sleep,
+    )
+}
+
+/// Writes the content of `N` displays. Unlike `write_frame()`, the display count is checked at
+/// compile time and no heap is needed.
+///
+/// * `display` - mutable reference to the display device, e.g. the Max7219 display driver
+/// * `data` - the content of each display
+pub fn write_display_data<D: MatrixDevice, const N: usize>(
+    display: &mut D,
+    data: &[SingleDisplayData; N],
+) -> Result<(), Error> {
+    check_display_count_of(display.module_count(), N)?;
+    for (i, display_data) in data.iter().enumerate() {
+        display.write_rows(i, display_data)?;
+    }
+    display.flush()
+}
+
+/// Like `show_moving_text_in_loop_with_delay()` for `N` displays but without a heap. The text is
+/// laid out in `buffer`, which needs one display more than `text` has characters. This only
+/// returns if writing to the display fails, if `buffer` is too small or if `text` is empty.
+/// **Make sure to call [`prepare_display`] first!**
+///
+/// ```no_run
+/// # use max_7219_led_matrix_util::{device::MatrixDevice, show_moving_text_in_loop_with_buffer};
+/// # use embedded_hal::blocking::delay::DelayMs;
+/// # fn f(display: &mut impl MatrixDevice, delay: &mut impl DelayMs<u32>) {
+/// let mut buffer = [[0; 8]; 16];
+/// show_moving_text_in_loop_with_buffer::<_, _, 4>(display, "HELLO", &mut buffer, delay, 50, None)
+///     .unwrap();
+/// # }
+/// ```
+///
+/// * `display` - mutable reference to the display device, e.g. the Max7219 display driver
+/// * `text` - the text to display
+/// * `buffer` - memory for the encoded text
+/// * `delay` - delay provider, e.g. from the HAL of the microcontroller
+/// * `ms_sleep` - timeout after each iteration
+/// * `gap_width` - (optional) set's the maximum width/count of empty cols between characters.
+///                 Downside is that spaces will be removed.
+pub fn show_moving_text_in_loop_with_buffer<D: MatrixDevice, T: DelayMs<u32>, const N: usize>(
+    display: &mut D,
+    text: &str,
+    buffer: &mut [SingleDisplayData],
+    delay: &mut T,
+    ms_sleep: u32,
+    gap_width: Option<usize>,
+) -> Result<(), Error> {
+    check_display_count_of(display.module_count(), N)?;
+    let len = layout_text_into(text, gap_width, buffer)?;
+    let data = &mut buffer[..len];
+
+    let mut shown: Option<Matrix<N>> = None;
+    while !data.is_empty() {
+        let mut window = Matrix::<N>::new();
+        window
+            .data
+            .iter_mut()
+            .zip(data.iter())
+            .for_each(|(window, data)| *window = *data);
+        write_matrix_changes(display, &window, shown.as_ref())?;
+        shown = Some(window);
+
+        shift_all_rows_one_bit_left(data);
+        delay.delay_ms(ms_sleep);
+    }
+    Ok(())
+}
 
+/// Like [`show_moving_text_in_loop_with_buffer`] but needs no buffer for the text. The cols of
+/// the text are encoded when they move onto the displays (see [`encoding::TextColumns`]), so the
+/// memory use doesn't grow with the length of the text. After the end of the text, the text
+/// starts again; a text that is shorter than the displays is repeated. This only returns if
+/// writing to the display fails or if `text` is empty.
+/// **Make sure to call [`prepare_display`] first!**
+///
+/// * `display` - mutable reference to the display device, e.g. the Max7219 display driver
+/// * `text` - the text to display
+/// * `delay` - delay provider, e.g. from the HAL of the microcontroller
+/// * `ms_sleep` - timeout after each iteration
+/// * `gap_width` - (optional) set's the maximum width/count of empty cols between characters.
+///                 Downside is that spaces will be removed.
+pub fn show_moving_text_in_loop_lazy<D: MatrixDevice, T: DelayMs<u32>, const N: usize>(
+    display: &mut D,
+    text: &str,
+    delay: &mut T,
+    ms_sleep: u32,
+    gap_width: Option<usize>,
+) -> Result<(), Error> {
+    check_display_count_of(display.module_count(), N)?;
+    let mut cols = encoding::TextColumns::new(text.chars(), gap_width).cycle();
+
+    let mut window = Matrix::<N>::new();
+    for _ in 0..window.width() {
+        match cols.next() {
+            Some(col) => window.push_col(col),
+            None => return Ok(()),
+        }
+    }
+
+    let mut shown: Option<Matrix<N>> = None;
     loop {
-        for (i, display_data) in display_data_vec.iter().enumerate().take(display_count) {
-            display.write_raw(i, display_data).unwrap();
+        write_matrix_changes(display, &window, shown.as_ref())?;
+        shown = Some(window);
+
+        window.push_col(cols.next().unwrap_or_default());
+        delay.delay_ms(ms_sleep);
+    }
+}
+
+/// Like `play_animation_with_delay()` but plays a sequence of [`Matrix<N>`](Matrix) instead of
+/// an `Animation`, so no heap is needed. Only the displays that changed are sent.
+/// **Make sure to call [`prepare_matrix`] first!**
+///
+/// * `display` - mutable reference to the display device, e.g. the Max7219 display driver
+/// * `matrices` - the content of each frame
+/// * `delay` - delay provider, e.g. from the HAL of the microcontroller
+/// * `ms_sleep` - timeout after each frame
+pub fn play_matrices<D: MatrixDevice, T: DelayMs<u32>, const N: usize>(
+    display: &mut D,
+    matrices: impl IntoIterator<Item = Matrix<N>>,
+    delay: &mut T,
+    ms_sleep: u32,
+) -> Result<(), Error> {
+    check_display_count_of(display.module_count(), N)?;
+    let mut shown: Option<Matrix<N>> = None;
+    for matrix in matrices {
+        write_matrix_changes(display, &matrix, shown.as_ref())?;
+        shown = Some(matrix);
+        delay.delay_ms(ms_sleep);
+    }
+    Ok(())
+}
+
+/// Writes `matrix` to the display but only sends the displays that changed compared to the
+/// `previous` matrix.
+fn write_matrix_changes<D: MatrixDevice, const N: usize>(
+    display: &mut D,
+    matrix: &Matrix<N>,
+    previous: Option<&Matrix<N>>,
+) -> Result<(), Error> {
+    for (i, display_data) in matrix.data.iter().enumerate() {
+        if previous.map_or(true, |previous| previous.data[i] != *display_data) {
+            display.write_rows(i, display_data)?;
         }
-        sleep(Duration::from_millis(ms_sleep));
-        // shift all rows one bit to the left
-        shift_all_rows_one_bit_left(&mut display_data_vec);
+    }
+    display.flush()
+}
+
+/// Writes a single [`Frame`] to the display. The intensity and the power state are only
+/// updated if the frame contains them.
+///
+/// * `display` - mutable reference to the display device, e.g. the Max7219 display driver
+/// * `frame` - the frame to display
+/// * `display_count` - count of displays connected to the MAX7219
+#[cfg(feature = "alloc")]
+pub fn write_frame<D: MatrixDevice>(
+    display: &mut D,
+    frame: &Frame,
+    display_count: usize,
+) -> Result<(), Error> {
+    check_display_count_of(display.module_count(), display_count)?;
+    write_frame_changes(display, frame, None, display_count)
+}
+
+/// Like [`write_frame`] but only sends what changed compared to the `previous` frame.
+#[cfg(feature = "alloc")]
+pub(crate) fn write_frame_changes<D: MatrixDevice>(
+    display: &mut D,
+    frame: &Frame,
+    previous: Option<&Frame>,
+    display_count: usize,
+) -> Result<(), Error> {
+    for change in frame_changes(frame, previous, display_count) {
+        match change {
+            FrameChange::PowerOff => display.power_off()?,
+            FrameChange::Rows(module, rows) => display.write_rows(module, rows)?,
+            FrameChange::Intensity(module, intensity) => {
+                display.set_intensity(module, intensity)?
+            }
+            FrameChange::PowerOn => display.power_on()?,
+        }
+    }
+    display.flush()
+}
+
+/// A write that is needed to show a [`Frame`].
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FrameChange<'a> {
+    /// Power off all modules.
+    PowerOff,
+    /// Write the rows of the module.
+    Rows(usize, &'a SingleDisplayData),
+    /// Set the intensity of the module.
+    Intensity(usize, u8),
+    /// Power on all modules.
+    PowerOn,
+}
+
+/// The writes that show `frame` on a display that shows the `previous` frame, in the order
+/// they must be sent. Shared by the sync and the async display functions.
+#[cfg(feature = "alloc")]
+pub(crate) fn frame_changes<'a>(
+    frame: &'a Frame,
+    previous: Option<&'a Frame>,
+    display_count: usize,
+) -> impl Iterator<Item = FrameChange<'a>> {
+    let power_changed = previous.map_or(true, |previous| previous.power != frame.power);
+    let power_off = (power_changed && frame.power == Some(false)).then(|| FrameChange::PowerOff);
+    let power_on = (power_changed && frame.power == Some(true)).then(|| FrameChange::PowerOn);
+
+    let modules =
+        frame
+            .data
+            .iter()
+            .enumerate()
+            .take(display_count)
+            .flat_map(move |(i, display_data)| {
+                let previous_data = previous.and_then(|previous| previous.data.get(i));
+                let rows = (previous_data != Some(display_data))
+                    .then(|| FrameChange::Rows(i, display_data));
+                let previous_intensity = previous.and_then(|previous| previous.intensity.get(i));
+                let intensity = frame
+                    .intensity
+                    .get(i)
+                    .copied()
+                    .flatten()
+                    .filter(|intensity| previous_intensity != Some(&Some(*intensity)))
+                    .map(|intensity| FrameChange::Intensity(i, intensity));
+                rows.into_iter().chain(intensity)
+            });
+    power_off.into_iter().chain(modules).chain(power_on)
+}
+
+/// Plays an [`Animation`] until it is finished. This never returns for endless animations,
+/// like a [`Scroller`] without a limited count of passes. Only the parts that changed between
+/// two frames are sent to the display.
+/// **Make sure to call `prepare_display()` first!**
+///
+/// * `display` - mutable reference to the display device, e.g. the Max7219 display driver
+/// * `animation` - the animation to play
+/// * `display_count` - count of displays connected to the MAX7219
+/// * `ms_sleep` - timeout after each frame
+#[cfg(feature = "std")]
+pub fn play_animation<D: MatrixDevice>(
+    display: &mut D,
+    animation: impl Animation,
+    display_count: usize,
+    ms_sleep: u64,
+) -> Result<(), Error> {
+    play(display, animation, display_count, || {
+        sleep(Duration::from_millis(ms_sleep))
+    })
+}
+
+/// Like `play_animation()` but waits with `delay` after each frame. This also works in `no_std`
+/// environments.
+///
+/// * `display` - mutable reference to the display device, e.g. the Max7219 display driver
+/// * `animation` - the animation to play
+/// * `display_count` - count of displays connected to the MAX7219
+/// * `delay` - delay provider, e.g. from the HAL of the microcontroller
+/// * `ms_sleep` - timeout after each frame
+#[cfg(feature = "alloc")]
+pub fn play_animation_with_delay<D: MatrixDevice, T: DelayMs<u32>>(
+    display: &mut D,
+    animation: impl Animation,
+    display_count: usize,
+    delay: &mut T,
+    ms_sleep: u32,
+) -> Result<(), Error> {
+    play(display, animation, display_count, || {
+        delay.delay_ms(ms_sleep)
+    })
+}
+
+/// Plays `animation` and calls `wait` after each frame.
+#[cfg(feature = "alloc")]
+pub(crate) fn play<D: MatrixDevice>(
+    display: &mut D,
+    mut animation: impl Animation,
+    display_count: usize,
+    mut wait: impl FnMut(),
+) -> Result<(), Error> {
+    check_display_count_of(display.module_count(), display_count)?;
+
+    let mut frame = Frame::new(display_count);
+    let mut shown: Option<Frame> = None;
+    while animation.next_frame(&mut frame) {
+        write_frame_changes(display, &frame, shown.as_ref(), display_count)?;
+        shown = Some(frame.clone());
+        wait();
+    }
+    Ok(())
+}
+
+/// Fails with [`Error::InvalidDisplayCount`] if `display_count` is `0` or greater than
+/// [`MAX_DISPLAYS`].
+pub(crate) const fn check_display_count(display_count: usize) -> Result<(), Error> {
+    if display_count == 0 || display_count > MAX_DISPLAYS {
+        Err(Error::InvalidDisplayCount(display_count))
+    } else {
+        Ok(())
+    }
+}
+
+/// Like [`check_display_count`], but a device that reports its module count (see
+/// [`MatrixDevice::module_count`]) may have more than [`MAX_DISPLAYS`] modules, e.g. several
+/// combined chains. Then `display_count` must not be greater than the module count.
+pub(crate) const fn check_display_count_of(
+    module_count: Option<usize>,
+    display_count: usize,
+) -> Result<(), Error> {
+    match module_count {
+        Some(count) if display_count == 0 || display_count > count => {
+            Err(Error::InvalidDisplayCount(display_count))
+        }
+        Some(_) => Ok(()),
+        None => check_display_count(display_count),
     }
 }
 
@@ -150,40 +577,92 @@ pub fn show_moving_text_in_loop(
 /// # Parameters
 /// - `display_data_arr`: Processed Display Data with letters
 /// - `min_gap_size`: Minimum gap size to preserve between symbols
+#[cfg(feature = "alloc")]
 pub fn remove_gaps_in_display_text(
     display_data_arr: &[SingleDisplayData],
     min_gap_size: usize,
 ) -> Vec<SingleDisplayData> {
-    // all data in one single vector; each SingleDisplayData is transposed at first
-    // => the vector contains all pixels but in (col1, colX, colN)-order.
-    let display_data_expanded: Vec<u8> = display_data_arr
-        .iter()
-        // transpose: rows become cols
-        .map(transpose_single_display_data)
-        .flat_map(|display_data| display_data.into_iter())
-        .collect();
+    // the result is at most one display longer
+    let mut shrinked_display_data = vec![[0; LED_SQUARE_MATRIX_DIM]; display_data_arr.len() + 1];
+    let len = remove_gaps_in_display_text_into(
+        display_data_arr,
+        min_gap_size,
+        &mut shrinked_display_data,
+    )
+    .unwrap();
+    shrinked_display_data.truncate(len);
+    shrinked_display_data
+}
 
-    let preserve_at_begin = display_data_expanded
-        .iter()
-        .take_while(|col| **col == 0)
-        .count();
-    let preserve_at_end = display_data_expanded
+/// Like `remove_gaps_in_display_text()` but writes the result into `buffer` and doesn't need a
+/// heap. Returns the count of displays written to `buffer`, which is at most one more than the
+/// length of `display_data_arr`. Fails with [`Error::TextTooLong`] if `buffer` is too small.
+pub fn remove_gaps_in_display_text_into(
+    display_data_arr: &[SingleDisplayData],
+    min_gap_size: usize,
+    buffer: &mut [SingleDisplayData],
+) -> Result<usize, Error> {
+    let cols = display_data_arr
         .iter()
-        .rev()
-        .take_while(|col| **col == 0)
-        .count();
+        .flat_map(transpose_single_display_data);
+    let mut shrinked = ColWriter::new(buffer);
+    remove_gaps_in_cols(cols, min_gap_size, |col| shrinked.push(col))?;
+    Ok(shrinked.displays())
+}
 
-    // This variable holds the whole display data (col1, colX, ..., colN) but with
-    // removed gaps in between. We keep empty cols at the begin.
-    let mut shrinked_display_data_expanded = vec![0_u8; preserve_at_begin];
+/// Encodes `text` into `buffer` like `encode_string()` and removes the gaps between the
+/// characters, if `gap_width` is set, like `remove_gaps_in_display_text()`. This doesn't need a
+/// heap. Returns the count of displays written to `buffer`. Fails with [`Error::TextTooLong`] if
+/// `buffer` is too small; it needs one display more than `text` has characters.
+pub fn layout_text_into(
+    text: &str,
+    gap_width: Option<usize>,
+    buffer: &mut [SingleDisplayData],
+) -> Result<usize, Error> {
+    match gap_width {
+        Some(gap_width) => {
+            let cols = text
+                .chars()
+                .flat_map(|c| transpose_single_display_data(&encode_char(c)));
+            let mut shrinked = ColWriter::new(buffer);
+            remove_gaps_in_cols(cols, gap_width, |col| shrinked.push(col))?;
+            Ok(shrinked.displays())
+        }
+        None => encoding::encode_string_into(text, buffer),
+    }
+}
+
+/// Removes the gaps in the display data given by its `cols` (in the order col1, colX, ..., colN)
+/// and passes the resulting cols to `push`, filled up to whole displays. See
+/// `remove_gaps_in_display_text()`.
+pub(crate) fn remove_gaps_in_cols<I: DoubleEndedIterator<Item = u8> + Clone, E>(
+    cols: I,
+    min_gap_size: usize,
+    mut push: impl FnMut(u8) -> Result<(), E>,
+) -> Result<(), E> {
+    let total_cols = cols.clone().count();
+
+    let preserve_at_begin = cols.clone().take_while(|col| *col == 0).count();
+    // nothing to shrink if all cols are empty
+    if preserve_at_begin == total_cols {
+        for _ in 0..total_cols {
+            push(0)?;
+        }
+        return Ok(());
+    }
+    let preserve_at_end = cols.clone().rev().take_while(|col| *col == 0).count();
+
+    // We keep empty cols at the begin.
+    for _ in 0..preserve_at_begin {
+        push(0)?;
+    }
+    let mut len = preserve_at_begin;
 
     let mut count_since_last_not_empty = 0;
-    let skip_begin = preserve_at_begin;
-    let skip_end = preserve_at_end;
-    let take_elements = display_data_expanded.len() - skip_end - skip_begin + 1;
+    let take_elements = total_cols - preserve_at_end - preserve_at_begin + 1;
 
     // iterate and find gaps in between that can be removed
-    for col in display_data_expanded.iter().take(take_elements).copied() {
+    for col in cols.take(take_elements) {
         if col == 0 {
             count_since_last_not_empty += 1;
         } else {
@@ -192,39 +671,67 @@ pub fn remove_gaps_in_display_text(
 
         // we preserve `min_gap_size` empty cols, after that, we skip them
         if count_since_last_not_empty <= min_gap_size {
-            shrinked_display_data_expanded.push(col);
+            push(col)?;
+            len += 1;
         }
     }
 
-    // keep empty cols at end
-    shrinked_display_data_expanded.extend_from_slice(&vec![0; preserve_at_end]);
-
-    // now transform again to Vec<SingleDisplayData>
-    // 1) check if length is multiple of eight
-    let add_to_multiple_of_8 = 8 - (shrinked_display_data_expanded.len() % 8);
-    shrinked_display_data_expanded.extend_from_slice(&vec![0; add_to_multiple_of_8]);
-
-    // display data without gaps in-between ready for display.
-    let shrinked_display_data = shrinked_display_data_expanded
-        .as_slice()
-        .chunks_exact(8)
-        .map(|cols_8| {
-            // transform to u8 array of length 8
-            let mut transposed_display: SingleDisplayData = [0; 8];
-            cols_8
-                .iter()
-                .copied()
-                .enumerate()
-                .for_each(|(i, col)| transposed_display[i] = col);
-            transpose_single_display_data(&transposed_display)
-        })
-        .collect();
+    // keep empty cols at end and fill up the last display
+    let end = len + preserve_at_end + 1;
+    let end = (end + LED_SQUARE_MATRIX_DIM - 1) / LED_SQUARE_MATRIX_DIM * LED_SQUARE_MATRIX_DIM;
+    for _ in len..end {
+        push(0)?;
+    }
+    Ok(())
+}
 
-    shrinked_display_data
+/// Writes display data col by col into a buffer.
+pub(crate) struct ColWriter<'a> {
+    buffer: &'a mut [SingleDisplayData],
+    /// Count of written cols.
+    len: usize,
+}
+
+impl<'a> ColWriter<'a> {
+    pub(crate) fn new(buffer: &'a mut [SingleDisplayData]) -> Self {
+        Self { buffer, len: 0 }
+    }
+
+    /// Count of the displays that were written to.
+    pub(crate) const fn displays(&self) -> usize {
+        (self.len + LED_SQUARE_MATRIX_DIM - 1) / LED_SQUARE_MATRIX_DIM
+    }
+
+    /// Fails with [`Error::TextTooLong`] if the buffer is full.
+    pub(crate) fn push(&mut self, col: u8) -> Result<(), Error> {
+        if self.len >= self.buffer.len() * LED_SQUARE_MATRIX_DIM {
+            return Err(Error::TextTooLong(self.len / LED_SQUARE_MATRIX_DIM + 1));
+        }
+        self.push_or_drop(col);
+        Ok(())
+    }
+
+    /// Like `push()` but drops `col` if the buffer is full.
+    pub(crate) fn push_or_drop(&mut self, col: u8) {
+        let x = self.len % LED_SQUARE_MATRIX_DIM;
+        let display = match self.buffer.get_mut(self.len / LED_SQUARE_MATRIX_DIM) {
+            Some(display) => display,
+            None => return,
+        };
+        if x == 0 {
+            *display = [0; LED_SQUARE_MATRIX_DIM];
+        }
+        for (y, row) in display.iter_mut().enumerate() {
+            if col & (0b1000_0000 >> y) != 0 {
+                *row |= 0b1000_0000 >> x;
+            }
+        }
+        self.len += 1;
+    }
 }
 
 /// This does a transpose operation on the [`SingleDisplayData`]-Matrix and is a helper function for
-/// [`remove_gaps_in_display_text`]. Cols become rows and rows become cols.
+/// `remove_gaps_in_display_text()`. Cols become rows and rows become cols.
 /// Example:
 /// ```
 /// let _ = [
@@ -275,6 +782,7 @@ pub fn transpose_single_display_data(data: &SingleDisplayData) -> SingleDisplayD
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::vec::Vec;
 
     #[test]
     fn test_move_all_bits_one_col_left() {
@@ -336,6 +844,28 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "alloc")]
+    fn test_frame_changes() {
+        let mut previous = Frame::new(2);
+        previous.power = Some(true);
+        let mut frame = previous.clone();
+        frame.data[1] = [0xFF; 8];
+        frame.intensity[0] = Some(3);
+        frame.power = Some(false);
+        let changes: Vec<_> = frame_changes(&frame, Some(&previous), 2).collect();
+        assert_eq!(
+            changes,
+            [
+                FrameChange::PowerOff,
+                FrameChange::Intensity(0, 3),
+                FrameChange::Rows(1, &[0xFF; 8]),
+            ]
+        );
+        assert_eq!(frame_changes(&frame, Some(&frame), 2).count(), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
     fn test_remove_gaps_in_display_text() {
         let vec = vec![
             [
@@ -347,7 +877,7 @@ mod tests {
                 0b10000000,
             ],
         ];
-        let expected = vec![
+        let expected = [
             [
                 0b10010000, 0b10010000, 0b10010000, 0b10010000, 0b10010000, 0b10010000, 0b10010000,
                 0b10010000,
@@ -368,4 +898,39 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_remove_gaps_in_empty_display_text() {
+        let empty = vec![[0; 8], [0; 8]];
+        assert_eq!(remove_gaps_in_display_text(&empty, 2), empty);
+    }
+
+    #[test]
+    fn test_layout_text_into() {
+        let mut buffer = [[0xFF; 8]; 3];
+        assert_eq!(layout_text_into("AB", None, &mut buffer).unwrap(), 2);
+        assert_eq!(buffer[..2], [encode_char('A'), encode_char('B')]);
+        assert!(matches!(
+            layout_text_into("ABCD", None, &mut buffer),
+            Err(Error::TextTooLong(4))
+        ));
+
+        // the same as `remove_gaps_in_display_text()`, one display more than chars
+        let mut buffer = [[0xFF; 8]; 3];
+        let len = layout_text_into("II", Some(1), &mut buffer).unwrap();
+        let mut expected = [[0; 8]; 3];
+        let expected_len = remove_gaps_in_display_text_into(
+            &[encode_char('I'), encode_char('I')],
+            1,
+            &mut expected,
+        )
+        .unwrap();
+        assert_eq!(len, expected_len);
+        assert_eq!(buffer[..len], expected[..len]);
+        assert!(matches!(
+            layout_text_into("II", Some(1), &mut buffer[..1]),
+            Err(Error::TextTooLong(_))
+        ));
+    }
 }
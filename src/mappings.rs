@@ -109,6 +109,38 @@ pub const ZERO: SingleDisplayData = [
 pub const ONE: SingleDisplayData = [
     0b00000100, 0b00011100, 0b00000100, 0b00000100, 0b00000100, 0b00000100, 0b00000100, 0b00000100,
 ];
+/// Number 2 mapping.
+pub const TWO: SingleDisplayData = [
+    0b00111000, 0b01000100, 0b00000100, 0b00001000, 0b00010000, 0b00100000, 0b01000000, 0b01111100,
+];
+/// Number 3 mapping.
+pub const THREE: SingleDisplayData = [
+    0b00111000, 0b01000100, 0b00000100, 0b00011000, 0b00000100, 0b00000100, 0b01000100, 0b00111000,
+];
+/// Number 4 mapping.
+pub const FOUR: SingleDisplayData = [
+    0b00001000, 0b00011000, 0b00101000, 0b01001000, 0b01111100, 0b00001000, 0b00001000, 0b00001000,
+];
+/// Number 5 mapping.
+pub const FIVE: SingleDisplayData = [
+    0b01111100, 0b01000000, 0b01000000, 0b01111000, 0b00000100, 0b00000100, 0b01000100, 0b00111000,
+];
+/// Number 6 mapping.
+pub const SIX: SingleDisplayData = [
+    0b00111000, 0b01000100, 0b01000000, 0b01111000, 0b01000100, 0b01000100, 0b01000100, 0b00111000,
+];
+/// Number 7 mapping.
+pub const SEVEN: SingleDisplayData = [
+    0b01111100, 0b00000100, 0b00000100, 0b00001000, 0b00010000, 0b00100000, 0b00100000, 0b00100000,
+];
+/// Number 8 mapping.
+pub const EIGHT: SingleDisplayData = [
+    0b00111000, 0b01000100, 0b01000100, 0b00111000, 0b01000100, 0b01000100, 0b01000100, 0b00111000,
+];
+/// Number 9 mapping.
+pub const NINE: SingleDisplayData = [
+    0b00111000, 0b01000100, 0b01000100, 0b01000100, 0b00111100, 0b00000100, 0b01000100, 0b00111000,
+];
 /// " " character mapping.
 pub const SPACE: SingleDisplayData = [0; 8];
 /// "." character mapping.
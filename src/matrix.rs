@@ -0,0 +1,192 @@
+//! [`Matrix`] is the content of a whole display chain with a module count that is known at
+//! compile time. It lives on the stack, so it also works in `no_std` environments without a
+//! heap, and a module count that doesn't match is a type error instead of a runtime error.
+//!
+//! [`crate::prepare_matrix`], [`Matrix::write`], [`crate::play_matrices`] and
+//! [`crate::show_moving_text_in_loop_lazy`] take the module count from `N` instead of a
+//! `display_count` argument.
+
+use crate::device::MatrixDevice;
+use crate::encoding::encode_string_into;
+use crate::mappings::SingleDisplayData;
+use crate::{
+    remove_gaps_in_cols, shift_all_rows_one_bit_left, shift_in_col, transpose_single_display_data,
+    write_display_data, ColWriter, Error, LED_SQUARE_MATRIX_DIM,
+};
+use core::convert::Infallible;
+
+/// The content of a chain of `N` displays. Index 0 is the leftmost display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Matrix<const N: usize> {
+    /// Pixel data of each display.
+    pub data: [SingleDisplayData; N],
+}
+
+impl<const N: usize> Matrix<N> {
+    /// Creates an empty (all pixels off) matrix.
+    pub const fn new() -> Self {
+        Self {
+            data: [[0; LED_SQUARE_MATRIX_DIM]; N],
+        }
+    }
+
+    /// Creates a matrix that shows `text`, one character per display. Fails with
+    /// [`Error::TextTooLong`] if `text` has more than `N` characters.
+    pub fn from_text(text: &str) -> Result<Self, Error> {
+        let mut matrix = Self::new();
+        encode_string_into(text, &mut matrix.data)?;
+        Ok(matrix)
+    }
+
+    /// Width of the matrix in pixels (columns).
+    pub const fn width(&self) -> usize {
+        N * LED_SQUARE_MATRIX_DIM
+    }
+
+    /// Whether all pixels are off.
+    pub fn is_empty(&self) -> bool {
+        self.data.iter().flatten().all(|row| *row == 0)
+    }
+
+    /// Shifts all rows one pixel to the left, see [`shift_all_rows_one_bit_left`].
+    pub fn shift_left(&mut self) {
+        shift_all_rows_one_bit_left(&mut self.data);
+    }
+
+    /// Shifts all rows one pixel to the left and adds `col` as the rightmost col. The leftmost
+    /// col is dropped. The most significant bit of `col` is the top row, e.g. a col of
+    /// [`crate::encoding::TextColumns`].
+    pub fn push_col(&mut self, col: u8) {
+        shift_in_col(&mut self.data, col);
+    }
+
+    /// Transposes each display, see [`transpose_single_display_data`].
+    pub fn transpose(&mut self) {
+        for display_data in &mut self.data {
+            *display_data = transpose_single_display_data(display_data);
+        }
+    }
+
+    /// Removes the gaps between symbols like `remove_gaps_in_display_text()`. The freed
+    /// columns at the right end are empty; columns that would end up after the right end are
+    /// dropped, as the matrix can't grow.
+    pub fn remove_gaps(&mut self, min_gap_size: usize) {
+        let data = self.data;
+        let cols = data.iter().flat_map(transpose_single_display_data);
+        self.data = Self::new().data;
+        let mut shrinked = ColWriter::new(&mut self.data);
+        let pushed = remove_gaps_in_cols(cols, min_gap_size, |col| {
+            shrinked.push_or_drop(col);
+            Ok::<_, Infallible>(())
+        });
+        match pushed {
+            Ok(()) => {}
+            Err(never) => match never {},
+        }
+    }
+
+    /// Writes the matrix to the first `N` displays of `display`.
+    ///
+    /// * `display` - mutable reference to the display device, e.g. the Max7219 display driver
+    pub fn write<D: MatrixDevice>(&self, display: &mut D) -> Result<(), Error> {
+        write_display_data(display, &self.data)
+    }
+}
+
+impl<const N: usize> Default for Matrix<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> From<[SingleDisplayData; N]> for Matrix<N> {
+    fn from(data: [SingleDisplayData; N]) -> Self {
+        Self { data }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::encode_char;
+    #[cfg(feature = "alloc")]
+    use crate::recording::RecordingDevice;
+    #[cfg(feature = "alloc")]
+    use embedded_hal::blocking::delay::DelayMs;
+
+    #[test]
+    fn test_matrix_shift_and_transpose() {
+        let mut matrix = Matrix::from([[0; 8], [0b1000_0001; 8]]);
+        matrix.shift_left();
+        assert_eq!(matrix.data, [[0b0000_0001; 8], [0b0000_0010; 8]]);
+
+        matrix.transpose();
+        assert_eq!(matrix.data[0], [0, 0, 0, 0, 0, 0, 0, 0xFF]);
+        assert!(!matrix.is_empty());
+        assert!(Matrix::<4>::new().is_empty());
+        assert_eq!(
+            Matrix::<2>::from_text("A").unwrap().data,
+            [encode_char('A'), [0; 8]]
+        );
+        assert!(matches!(
+            Matrix::<2>::from_text("ABC"),
+            Err(Error::TextTooLong(3))
+        ));
+    }
+
+    #[cfg(feature = "alloc")]
+    struct NoDelay;
+
+    #[cfg(feature = "alloc")]
+    impl DelayMs<u32> for NoDelay {
+        fn delay_ms(&mut self, _ms: u32) {}
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_play_matrices() {
+        let mut device = RecordingDevice::new(2);
+        crate::prepare_matrix::<_, 2>(&mut device, 0x0F).unwrap();
+        let writes = device.writes().len();
+        let first = Matrix::from([[0xFF; 8], [0; 8]]);
+        let second = Matrix::from([[0xFF; 8], [0x0F; 8]]);
+        crate::play_matrices(&mut device, [first, second], &mut NoDelay, 10).unwrap();
+        // both displays of the first frame and only the second display of the second frame
+        assert_eq!(device.writes().len(), writes + 3 * LED_SQUARE_MATRIX_DIM);
+        assert_eq!(device.frames().last().unwrap(), &second.data[..]);
+
+        assert!(matches!(
+            crate::play_matrices(&mut device, [Matrix::<3>::new()], &mut NoDelay, 10),
+            Err(Error::InvalidDisplayCount(3))
+        ));
+    }
+
+    #[test]
+    fn test_matrix_push_col() {
+        let mut matrix = Matrix::from([[0b1000_0000; 8], [0b1000_0000; 8]]);
+        matrix.push_col(0b1000_0001);
+        assert_eq!(
+            matrix.data,
+            [[0b0000_0001; 8], [1, 0, 0, 0, 0, 0, 0, 0b0000_0001]]
+        );
+    }
+
+    #[test]
+    fn test_matrix_remove_gaps_like_slice_version() {
+        let mut matrix = Matrix::<3>::from_text("I I").unwrap();
+        let mut expected = [[0; 8]; 4];
+        crate::remove_gaps_in_display_text_into(&matrix.data, 1, &mut expected).unwrap();
+        matrix.remove_gaps(1);
+        assert_eq!(matrix.data[..], expected[..3]);
+
+        // the content fills all cols, so the result would be one display longer
+        let mut full = Matrix::from([[0xFF; 8]; 2]);
+        full.remove_gaps(1);
+        assert_eq!(full.data, [[0xFF; 8]; 2]);
+
+        // the empty col at the begin is kept, so the last col doesn't fit anymore
+        let mut shifted = Matrix::from([[0b0111_1111; 8]]);
+        shifted.remove_gaps(1);
+        assert_eq!(shifted.data, [[0b0011_1111; 8]]);
+    }
+}
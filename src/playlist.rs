@@ -0,0 +1,319 @@
+//! Message playlist that plays several items after each other, frame by frame. Each item has
+//! an optional entry effect, a hold duration and a repeat count. A [`Playlist`] is an
+//! [`Animation`] itself, so it can be played with [`crate::play_animation_with_delay`] or
+//! chained with other animations.
+
+use crate::animation::{layout_text, Animation, Frame, Hold, Scroller};
+use crate::mappings::SingleDisplayData;
+use crate::transition::{Transition, TransitionKind};
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+
+/// Creates a new instance of an animation for each play of a [`PlaylistItem`].
+type AnimationFactory = Box<dyn FnMut() -> Box<dyn Animation>>;
+
+/// Content of a [`PlaylistItem`].
+enum Content {
+    /// Static text.
+    Text(String, Option<usize>),
+    /// Text that scrolls through the display chain once.
+    ScrollingText(String, Option<usize>),
+    /// Static display data.
+    Bitmap(Vec<SingleDisplayData>),
+    /// Any animation.
+    Animation(AnimationFactory),
+}
+
+impl fmt::Debug for Content {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Text(text, gap_width) => {
+                f.debug_tuple("Text").field(text).field(gap_width).finish()
+            }
+            Self::ScrollingText(text, gap_width) => f
+                .debug_tuple("ScrollingText")
+                .field(text)
+                .field(gap_width)
+                .finish(),
+            Self::Bitmap(data) => f.debug_tuple("Bitmap").field(data).finish(),
+            Self::Animation(_) => f.debug_tuple("Animation").finish(),
+        }
+    }
+}
+
+/// A single item of a [`Playlist`].
+#[derive(Debug)]
+pub struct PlaylistItem {
+    content: Content,
+    entry: Option<(TransitionKind, usize)>,
+    hold: usize,
+    repeat: usize,
+}
+
+impl PlaylistItem {
+    const fn new(content: Content) -> Self {
+        Self {
+            content,
+            entry: None,
+            hold: 0,
+            repeat: 1,
+        }
+    }
+
+    /// Static text. Set a hold duration with [`Self::hold`], otherwise it is not visible.
+    ///
+    /// * `text` - the text to display
+    /// * `gap_width` - (optional) set's the maximum width/count of empty cols between characters.
+    ///                 Downside is that spaces will be removed.
+    pub fn text(text: &str, gap_width: Option<usize>) -> Self {
+        Self::new(Content::Text(text.to_string(), gap_width))
+    }
+
+    /// Text that scrolls once through the display chain, like in
+    /// `show_moving_text_in_loop()`.
+    ///
+    /// * `text` - the text to display
+    /// * `gap_width` - (optional) set's the maximum width/count of empty cols between characters.
+    ///                 Downside is that spaces will be removed.
+    pub fn scrolling_text(text: &str, gap_width: Option<usize>) -> Self {
+        Self::new(Content::ScrollingText(text.to_string(), gap_width))
+    }
+
+    /// Static display data. Set a hold duration with [`Self::hold`], otherwise it is not visible.
+    pub const fn bitmap(data: Vec<SingleDisplayData>) -> Self {
+        Self::new(Content::Bitmap(data))
+    }
+
+    /// Any animation. `factory` is called each time the item is played.
+    pub fn animation<F, A>(mut factory: F) -> Self
+    where
+        F: FnMut() -> A + 'static,
+        A: Animation + 'static,
+    {
+        Self::new(Content::Animation(Box::new(move || Box::new(factory()))))
+    }
+
+    /// Transition that is played before the content.
+    ///
+    /// * `kind` - the effect
+    /// * `duration` - duration in frames
+    pub const fn entry(mut self, kind: TransitionKind, duration: usize) -> Self {
+        self.entry = Some((kind, duration));
+        self
+    }
+
+    /// Frames the content stays on the display. For scrolling text and animations, this is
+    /// the time their last frame stays on the display.
+    pub const fn hold(mut self, frames: usize) -> Self {
+        self.hold = frames;
+        self
+    }
+
+    /// How often the item (including its entry effect) is played.
+    pub const fn repeat(mut self, times: usize) -> Self {
+        self.repeat = times;
+        self
+    }
+
+    /// Creates the animation for one play of this item.
+    fn build(&mut self, frame: &Frame) -> Box<dyn Animation> {
+        let hold = Hold::new(self.hold);
+        let (first_frame, content): (Vec<SingleDisplayData>, Box<dyn Animation>) =
+            match &mut self.content {
+                Content::Text(text, gap_width) => {
+                    let data = layout_text(text, *gap_width);
+                    (data.clone(), Box::new(hold.content(data)))
+                }
+                Content::Bitmap(data) => (data.clone(), Box::new(hold.content(data.clone()))),
+                Content::ScrollingText(text, gap_width) => {
+                    let scroller = Scroller::new(text, *gap_width).passes(1);
+                    (
+                        scroller.current_frame(frame.data.len()),
+                        Box::new(scroller.then(hold)),
+                    )
+                }
+                Content::Animation(factory) => {
+                    let mut animation = factory();
+                    // the entry effect needs the first frame of the animation as target
+                    let mut first_frame = frame.clone();
+                    if self.entry.is_some() && animation.next_frame(&mut first_frame) {
+                        (first_frame.data, Box::new(animation.then(hold)))
+                    } else {
+                        (Vec::new(), Box::new(animation.then(hold)))
+                    }
+                }
+            };
+        match self.entry {
+            Some((kind, duration)) => {
+                Box::new(Transition::new(kind, duration, first_frame).then(content))
+            }
+            None => content,
+        }
+    }
+}
+
+/// Plays [`PlaylistItem`]s after each other.
+#[derive(Debug)]
+pub struct Playlist {
+    items: Vec<PlaylistItem>,
+    /// Remaining passes through all items; `None` for an endless playlist.
+    passes: Option<usize>,
+    /// Index of the current item.
+    index: usize,
+    /// Plays of the current item so far.
+    plays: usize,
+    current: Option<CurrentItem>,
+    /// Whether the current pass through all items rendered at least one frame.
+    rendered: bool,
+}
+
+/// Wrapper, because `Box<dyn Animation>` doesn't implement [`fmt::Debug`].
+struct CurrentItem(Box<dyn Animation>);
+
+impl fmt::Debug for CurrentItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("CurrentItem").finish()
+    }
+}
+
+impl Default for Playlist {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Playlist {
+    /// Creates an empty playlist that is played once.
+    pub const fn new() -> Self {
+        Self {
+            items: Vec::new(),
+            passes: Some(1),
+            index: 0,
+            plays: 0,
+            current: None,
+            rendered: false,
+        }
+    }
+
+    /// Adds an item at the end of the playlist.
+    pub fn item(mut self, item: PlaylistItem) -> Self {
+        self.items.push(item);
+        self
+    }
+
+    /// Plays all items `times` times.
+    pub const fn repeat(mut self, times: usize) -> Self {
+        self.passes = Some(times);
+        self
+    }
+
+    /// Plays all items again and again.
+    pub const fn endless(mut self) -> Self {
+        self.passes = None;
+        self
+    }
+
+    /// Moves to the next item. Returns `false` if the playlist is finished.
+    fn advance(&mut self) -> bool {
+        self.plays += 1;
+        if self.plays < self.items[self.index].repeat {
+            return true;
+        }
+        self.plays = 0;
+        self.index += 1;
+        if self.index < self.items.len() {
+            return true;
+        }
+        self.index = 0;
+        if let Some(passes) = self.passes.as_mut() {
+            *passes -= 1;
+        }
+        // stop, if a whole pass didn't render anything; this would loop forever otherwise
+        let rendered = core::mem::replace(&mut self.rendered, false);
+        rendered && self.passes != Some(0)
+    }
+}
+
+impl Animation for Playlist {
+    fn next_frame(&mut self, frame: &mut Frame) -> bool {
+        if self.items.is_empty() || self.passes == Some(0) {
+            return false;
+        }
+        loop {
+            if self.items[self.index].repeat == 0 {
+                if !self.advance() {
+                    return false;
+                }
+                continue;
+            }
+            let current = match self.current.as_mut() {
+                Some(current) => current,
+                None => {
+                    let animation = self.items[self.index].build(frame);
+                    self.current.insert(CurrentItem(animation))
+                }
+            };
+            if current.0.next_frame(frame) {
+                self.rendered = true;
+                return true;
+            }
+            self.current = None;
+            if !self.advance() {
+                return false;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::encode_char;
+    use crate::transition::Direction;
+
+    fn frames(mut playlist: Playlist, display_count: usize) -> Vec<Vec<SingleDisplayData>> {
+        let mut frame = Frame::new(display_count);
+        let mut frames = Vec::new();
+        while playlist.next_frame(&mut frame) {
+            frames.push(frame.data.clone());
+        }
+        frames
+    }
+
+    #[test]
+    fn test_playlist_plays_items_in_order() {
+        let playlist = Playlist::new()
+            .item(PlaylistItem::text("A", None).hold(2))
+            .item(
+                PlaylistItem::bitmap(vec![[0xFF; 8]])
+                    .entry(TransitionKind::Push(Direction::Left), 4)
+                    .hold(1),
+            )
+            .item(PlaylistItem::scrolling_text("B", None));
+        let frames = frames(playlist, 1);
+        assert_eq!(frames.len(), 2 + 4 + 1 + 8);
+        assert_eq!(frames[0], [encode_char('A')]);
+        assert_eq!(frames[5], [[0xFF; 8]]);
+        assert_eq!(frames[6], [[0xFF; 8]]);
+        assert_eq!(frames[7], [encode_char('B')]);
+    }
+
+    #[test]
+    fn test_playlist_repeat() {
+        let playlist = Playlist::new()
+            .item(PlaylistItem::text("A", None).hold(1).repeat(3))
+            .item(PlaylistItem::animation(|| Hold::new(2)).repeat(0))
+            .repeat(2);
+        assert_eq!(frames(playlist, 1).len(), 6);
+    }
+
+    #[test]
+    fn test_empty_endless_playlist_stops() {
+        let playlist = Playlist::new()
+            .item(PlaylistItem::text("A", None))
+            .endless();
+        assert!(frames(playlist, 1).is_empty());
+    }
+}
@@ -0,0 +1,237 @@
+//! A [`MatrixDevice`] that records every register write. Useful for tests: the recorded writes
+//! can be checked directly, or the visible frames can be rebuilt and compared against
+//! ASCII-art golden files (see [`frames_to_ascii`]).
+
+use crate::device::MatrixDevice;
+use crate::mappings::SingleDisplayData;
+use crate::{Error, LED_SQUARE_MATRIX_DIM};
+use alloc::string::String;
+use alloc::vec::Vec;
+use max7219::DecodeMode;
+
+pub use crate::driver::Register;
+
+/// A single recorded register write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegisterWrite {
+    /// Number of the frame, i.e. the count of [`MatrixDevice::flush`] calls before this write.
+    pub frame: usize,
+    /// Index of the module.
+    pub module: usize,
+    /// The register.
+    pub register: Register,
+    /// The written value.
+    pub value: u8,
+}
+
+/// A [`MatrixDevice`] that records all register writes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordingDevice {
+    module_count: usize,
+    writes: Vec<RegisterWrite>,
+    frame: usize,
+}
+
+impl RecordingDevice {
+    /// Creates a device with `module_count` modules and no recorded writes.
+    pub const fn new(module_count: usize) -> Self {
+        Self {
+            module_count,
+            writes: Vec::new(),
+            frame: 0,
+        }
+    }
+
+    /// All recorded writes in the order they happened.
+    pub fn writes(&self) -> &[RegisterWrite] {
+        &self.writes
+    }
+
+    /// Rebuilds the content that was visible after each frame, i.e. after each call of
+    /// [`MatrixDevice::flush`]. Modules in shutdown mode are dark, rows after the scan limit
+    /// are not shown and modules in display test mode are completely lit.
+    pub fn frames(&self) -> Vec<Vec<SingleDisplayData>> {
+        let mut rows = vec![[0; LED_SQUARE_MATRIX_DIM]; self.module_count];
+        let mut power = vec![false; self.module_count];
+        let mut scan_limit = vec![7; self.module_count];
+        let mut display_test = vec![false; self.module_count];
+        let mut frames = Vec::new();
+
+        let mut writes = self.writes.iter().peekable();
+        for frame in 0..self.frame {
+            while let Some(write) = writes.next_if(|write| write.frame == frame) {
+                match write.register {
+                    Register::Digit(digit) => rows[write.module][usize::from(digit)] = write.value,
+                    Register::Shutdown => power[write.module] = write.value != 0,
+                    Register::ScanLimit => scan_limit[write.module] = write.value,
+                    Register::DisplayTest => display_test[write.module] = write.value != 0,
+                    _ => {}
+                }
+            }
+            let visible = (0..self.module_count)
+                .map(|module| {
+                    let mut visible = [0; LED_SQUARE_MATRIX_DIM];
+                    if display_test[module] {
+                        visible = [0xFF; LED_SQUARE_MATRIX_DIM];
+                    } else if power[module] {
+                        let shown = usize::from(scan_limit[module]) + 1;
+                        visible[..shown].copy_from_slice(&rows[module][..shown]);
+                    }
+                    visible
+                })
+                .collect();
+            frames.push(visible);
+        }
+        frames
+    }
+
+    fn record(&mut self, module: usize, register: Register, value: u8) -> Result<(), Error> {
+        if module >= self.module_count {
+            return Err(Error::InvalidModule(module));
+        }
+        self.writes.push(RegisterWrite {
+            frame: self.frame,
+            module,
+            register,
+            value,
+        });
+        Ok(())
+    }
+}
+
+impl MatrixDevice for RecordingDevice {
+    fn write_rows(&mut self, module: usize, rows: &SingleDisplayData) -> Result<(), Error> {
+        for (digit, row) in rows.iter().enumerate() {
+            self.record(module, Register::Digit(digit as u8), *row)?;
+        }
+        Ok(())
+    }
+
+    fn set_intensity(&mut self, module: usize, intensity: u8) -> Result<(), Error> {
+        self.record(module, Register::Intensity, intensity)
+    }
+
+    fn power_on(&mut self) -> Result<(), Error> {
+        (0..self.module_count).try_for_each(|module| self.record(module, Register::Shutdown, 1))
+    }
+
+    fn power_off(&mut self) -> Result<(), Error> {
+        (0..self.module_count).try_for_each(|module| self.record(module, Register::Shutdown, 0))
+    }
+
+    fn set_scan_limit(&mut self, module: usize, limit: u8) -> Result<(), Error> {
+        self.record(module, Register::ScanLimit, limit)
+    }
+
+    fn set_decode_mode(&mut self, module: usize, mode: DecodeMode) -> Result<(), Error> {
+        self.record(module, Register::DecodeMode, mode as u8)
+    }
+
+    fn set_display_test(&mut self, module: usize, on: bool) -> Result<(), Error> {
+        self.record(module, Register::DisplayTest, u8::from(on))
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        self.frame += 1;
+        Ok(())
+    }
+
+    fn module_count(&self) -> Option<usize> {
+        Some(self.module_count)
+    }
+}
+
+/// Renders frames as ASCII art: `#` for lit pixels and `.` for other pixels. Each frame has one
+/// line per row; the modules are separated by a space and the frames by an empty line.
+pub fn frames_to_ascii(frames: &[Vec<SingleDisplayData>]) -> String {
+    let mut output = String::new();
+    for (i, frame) in frames.iter().enumerate() {
+        if i > 0 {
+            output.push('\n');
+        }
+        for y in 0..LED_SQUARE_MATRIX_DIM {
+            for (module, rows) in frame.iter().enumerate() {
+                if module > 0 {
+                    output.push(' ');
+                }
+                for x in 0..LED_SQUARE_MATRIX_DIM {
+                    let on = rows[y] & (0b1000_0000 >> x) != 0;
+                    output.push(if on { '#' } else { '.' });
+                }
+            }
+            output.push('\n');
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::animation::{Animation, Hold, Scroller};
+    use crate::effects::Blink;
+    use crate::encoding::encode_string;
+    use crate::transition::{Direction, Transition, TransitionKind};
+    use crate::{play_animation_with_delay, prepare_display};
+    use embedded_hal::blocking::delay::DelayMs;
+
+    struct NoDelay;
+
+    impl DelayMs<u32> for NoDelay {
+        fn delay_ms(&mut self, _ms: u32) {}
+    }
+
+    /// Plays `animation` on a prepared device and returns the visible frames as ASCII art.
+    fn record(animation: impl Animation, module_count: usize) -> String {
+        let mut device = RecordingDevice::new(module_count);
+        prepare_display(&mut device, module_count, 0x0F).unwrap();
+        play_animation_with_delay(&mut device, animation, module_count, &mut NoDelay, 0).unwrap();
+        // skip the frame of `prepare_display()`
+        frames_to_ascii(&device.frames()[1..])
+    }
+
+    #[test]
+    fn test_prepare_display_sequence() {
+        let mut device = RecordingDevice::new(2);
+        prepare_display(&mut device, 2, 0x0A).unwrap();
+        let writes: Vec<_> = device
+            .writes()
+            .iter()
+            .filter(|write| !matches!(write.register, Register::Digit(_)))
+            .map(|write| (write.module, write.register, write.value))
+            .collect();
+        assert_eq!(
+            writes,
+            [
+                (0, Register::Shutdown, 1),
+                (1, Register::Shutdown, 1),
+                (0, Register::DecodeMode, 0),
+                (0, Register::Intensity, 0x0A),
+                (1, Register::DecodeMode, 0),
+                (1, Register::Intensity, 0x0A),
+            ]
+        );
+        assert_eq!(device.frames(), [[[0; 8], [0; 8]]]);
+    }
+
+    #[test]
+    fn test_golden_scroll() {
+        let scroller = Scroller::new("HI", Some(1)).passes(1);
+        let actual = record(scroller, 2);
+        assert_eq!(actual, include_str!("../tests/golden/scroll.txt"));
+    }
+
+    #[test]
+    fn test_golden_push_and_blink() {
+        let animation = Hold::new(1)
+            .content(encode_string("OK"))
+            .then(Transition::new(
+                TransitionKind::Push(Direction::Up),
+                4,
+                encode_string("GO"),
+            ))
+            .then(Blink::flash(1, 2));
+        let actual = record(animation, 2);
+        assert_eq!(actual, include_str!("../tests/golden/push_and_blink.txt"));
+    }
+}
@@ -0,0 +1,272 @@
+//! Recovery from display glitches. On long cables, noise can make a MAX7219 latch wrong register
+//! values, so that a module ends up in test mode, in shutdown mode or with a wrong decode mode.
+//! [`Refreshing`] remembers everything that was written and re-sends it periodically, so that
+//! such a glitch only lasts until the next refresh.
+
+use crate::device::MatrixDevice;
+use crate::mappings::SingleDisplayData;
+use crate::Error;
+use alloc::vec::Vec;
+use max7219::DecodeMode;
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+
+/// When [`Refreshing`] re-sends the registers and the content. The refresh is done when a frame
+/// is finished, i.e. during [`MatrixDevice::flush`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RefreshInterval {
+    /// After every `n`-th frame, e.g. `1` refreshes after each frame. `0` disables the refresh;
+    /// call [`Refreshing::refresh`] instead.
+    Frames(usize),
+    /// After the given time passed.
+    #[cfg(feature = "std")]
+    Time(Duration),
+}
+
+/// The last values written to the registers of a module; `None` if unknown.
+#[derive(Debug, Clone, Copy, Default)]
+struct ModuleRegisters {
+    rows: Option<SingleDisplayData>,
+    intensity: Option<u8>,
+    scan_limit: Option<u8>,
+    decode_mode: Option<u8>,
+    display_test: bool,
+}
+
+/// A [`MatrixDevice`] that re-sends the config registers (test mode, decode mode, scan limit,
+/// intensity and shutdown) and the content of all modules at a [`RefreshInterval`]. Only
+/// registers that were written through it are re-sent. See [`crate::Display::with_refresh`].
+#[derive(Debug)]
+pub struct Refreshing<D> {
+    device: D,
+    interval: RefreshInterval,
+    modules: Vec<ModuleRegisters>,
+    power: Option<bool>,
+    /// Frames since the last refresh.
+    frames: usize,
+    #[cfg(feature = "std")]
+    last_refresh: Instant,
+}
+
+impl<D: MatrixDevice> Refreshing<D> {
+    /// Wraps `device` with `module_count` modules.
+    pub fn new(device: D, module_count: usize, interval: RefreshInterval) -> Self {
+        Self {
+            device,
+            interval,
+            modules: vec![ModuleRegisters::default(); module_count],
+            power: None,
+            frames: 0,
+            #[cfg(feature = "std")]
+            last_refresh: Instant::now(),
+        }
+    }
+
+    /// The interval of the refresh.
+    pub fn interval(&self) -> RefreshInterval {
+        self.interval
+    }
+
+    /// Sets the interval of the refresh.
+    pub fn set_interval(&mut self, interval: RefreshInterval) {
+        self.interval = interval;
+    }
+
+    /// The underlying device.
+    pub fn device(&self) -> &D {
+        &self.device
+    }
+
+    /// The underlying device. Writes to it are not re-sent by the refresh.
+    pub fn device_mut(&mut self) -> &mut D {
+        &mut self.device
+    }
+
+    /// Returns the underlying device.
+    pub fn into_inner(self) -> D {
+        self.device
+    }
+
+    /// Re-sends all known registers and the content of all modules now.
+    pub fn refresh(&mut self) -> Result<(), Error> {
+        for (module, registers) in self.modules.iter().enumerate() {
+            self.device
+                .set_display_test(module, registers.display_test)?;
+            if let Some(mode) = registers.decode_mode {
+                self.device.set_decode_mode(module, decode_mode(mode))?;
+            }
+            if let Some(limit) = registers.scan_limit {
+                self.device.set_scan_limit(module, limit)?;
+            }
+            if let Some(intensity) = registers.intensity {
+                self.device.set_intensity(module, intensity)?;
+            }
+            if let Some(rows) = &registers.rows {
+                self.device.write_rows(module, rows)?;
+            }
+        }
+        match self.power {
+            Some(true) => self.device.power_on()?,
+            Some(false) => self.device.power_off()?,
+            None => {}
+        }
+        self.frames = 0;
+        #[cfg(feature = "std")]
+        {
+            self.last_refresh = Instant::now();
+        }
+        self.device.flush()
+    }
+
+    fn is_due(&self) -> bool {
+        match self.interval {
+            RefreshInterval::Frames(frames) => frames != 0 && self.frames >= frames,
+            #[cfg(feature = "std")]
+            RefreshInterval::Time(time) => self.last_refresh.elapsed() >= time,
+        }
+    }
+
+    fn registers(&mut self, module: usize) -> Result<&mut ModuleRegisters, Error> {
+        self.modules
+            .get_mut(module)
+            .ok_or(Error::InvalidModule(module))
+    }
+}
+
+/// The [`DecodeMode`] with the given register value.
+const fn decode_mode(value: u8) -> DecodeMode {
+    match value {
+        0x01 => DecodeMode::CodeBDigit0,
+        0x0F => DecodeMode::CodeBDigits3_0,
+        0xFF => DecodeMode::CodeBDigits7_0,
+        _ => DecodeMode::NoDecode,
+    }
+}
+
+impl<D: MatrixDevice> MatrixDevice for Refreshing<D> {
+    fn write_rows(&mut self, module: usize, rows: &SingleDisplayData) -> Result<(), Error> {
+        self.registers(module)?.rows = Some(*rows);
+        self.device.write_rows(module, rows)
+    }
+
+    fn set_intensity(&mut self, module: usize, intensity: u8) -> Result<(), Error> {
+        self.registers(module)?.intensity = Some(intensity);
+        self.device.set_intensity(module, intensity)
+    }
+
+    fn power_on(&mut self) -> Result<(), Error> {
+        self.power = Some(true);
+        self.device.power_on()
+    }
+
+    fn power_off(&mut self) -> Result<(), Error> {
+        self.power = Some(false);
+        self.device.power_off()
+    }
+
+    fn set_scan_limit(&mut self, module: usize, limit: u8) -> Result<(), Error> {
+        self.device.set_scan_limit(module, limit)?;
+        self.registers(module)?.scan_limit = Some(limit);
+        Ok(())
+    }
+
+    fn set_decode_mode(&mut self, module: usize, mode: DecodeMode) -> Result<(), Error> {
+        self.registers(module)?.decode_mode = Some(mode as u8);
+        self.device.set_decode_mode(module, mode)
+    }
+
+    fn set_display_test(&mut self, module: usize, on: bool) -> Result<(), Error> {
+        self.registers(module)?.display_test = on;
+        self.device.set_display_test(module, on)
+    }
+
+    fn clear(&mut self, module: usize) -> Result<(), Error> {
+        self.registers(module)?.rows = Some(SingleDisplayData::default());
+        self.device.clear(module)
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        self.frames += 1;
+        if self.is_due() {
+            self.refresh()
+        } else {
+            self.device.flush()
+        }
+    }
+
+    fn module_count(&self) -> Option<usize> {
+        Some(self.modules.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::animation::Frame;
+    use crate::device::MemoryDevice;
+    use crate::recording::{RecordingDevice, Register};
+    use crate::{prepare_display, write_frame};
+
+    #[test]
+    fn test_refresh_after_frames() {
+        let mut device = Refreshing::new(RecordingDevice::new(1), 1, RefreshInterval::Frames(3));
+        prepare_display(&mut device, 1, 0x05).unwrap();
+        let mut frame = Frame::new(1);
+        frame.data[0] = [0xAA; 8];
+        write_frame(&mut device, &frame, 1).unwrap();
+        let writes = device.device().writes().len();
+        write_frame(&mut device, &frame, 1).unwrap();
+
+        // the third frame re-sends everything
+        let registers = device.device().writes()[writes + 8..]
+            .iter()
+            .map(|write| (write.register, write.value))
+            .collect::<Vec<_>>();
+        assert_eq!(registers[0], (Register::DisplayTest, 0));
+        assert_eq!(registers[1], (Register::DecodeMode, 0));
+        assert_eq!(registers[2], (Register::Intensity, 0x05));
+        assert_eq!(registers[3], (Register::Digit(0), 0xAA));
+        assert_eq!(registers[11], (Register::Shutdown, 1));
+        assert_eq!(device.device().frames().last().unwrap()[0], [0xAA; 8]);
+    }
+
+    #[test]
+    fn test_refresh_every_nth_frame() {
+        let mut device = Refreshing::new(RecordingDevice::new(1), 1, RefreshInterval::Frames(2));
+        device.set_display_test(0, false).unwrap();
+        let mut refreshed = Vec::new();
+        for _ in 0..5 {
+            let writes = device.device().writes().len();
+            device.flush().unwrap();
+            refreshed.push(device.device().writes().len() > writes);
+        }
+        assert_eq!(refreshed, [false, true, false, true, false]);
+
+        device.set_interval(RefreshInterval::Frames(0));
+        let writes = device.device().writes().len();
+        for _ in 0..5 {
+            device.flush().unwrap();
+        }
+        assert_eq!(device.device().writes().len(), writes);
+    }
+
+    #[test]
+    fn test_refresh_recovers_from_glitch() {
+        let mut device = Refreshing::new(MemoryDevice::new(2), 2, RefreshInterval::Frames(1));
+        prepare_display(&mut device, 2, 0x0F).unwrap();
+        device.set_scan_limit(1, 3).unwrap();
+        let mut frame = Frame::new(2);
+        frame.data[1] = [0xFF; 8];
+        write_frame(&mut device, &frame, 2).unwrap();
+        let expected = device.device().clone();
+
+        // noise on the cable
+        let memory = device.device_mut();
+        memory.power = false;
+        memory.modules[0].display_test = true;
+        memory.modules[1].decode_mode = 0xFF;
+        memory.modules[1].rows[2] = 0;
+        device.flush().unwrap();
+        assert_eq!(device.device(), &expected);
+    }
+}
@@ -0,0 +1,216 @@
+//! Self test for the installation of a display chain. It helps to check the wiring order, the
+//! orientation of the modules and for dead LEDs:
+//!
+//! 1. the display test mode of the MAX7219 lights all LEDs with the highest intensity,
+//! 2. each module shows its index (module 0 is the first module of the chain),
+//! 3. each module in turn is lit completely with its index left dark,
+//! 4. each row from the top to the bottom is lit on all modules,
+//! 5. each column from the left to the right is lit on all modules.
+//!
+//! The displays are cleared afterwards. The speed is set by the time each step is shown.
+
+use crate::animation::{Animation, Frame};
+use crate::brightness::MAX_INTENSITY;
+use crate::device::MatrixDevice;
+use crate::encoding::encode_char;
+use crate::mappings::SingleDisplayData;
+use crate::{check_display_count_of, play, prepare_display, Error, LED_SQUARE_MATRIX_DIM};
+use embedded_hal::blocking::delay::DelayMs;
+#[cfg(feature = "std")]
+use std::{thread::sleep, time::Duration};
+
+/// The steps of the self test after the display test mode as [`Animation`]; one frame per step.
+/// The module count is taken from the frame.
+#[derive(Debug, Clone, Default)]
+pub struct SelfTest {
+    step: usize,
+}
+
+impl SelfTest {
+    /// Creates the animation.
+    pub const fn new() -> Self {
+        Self { step: 0 }
+    }
+}
+
+impl Animation for SelfTest {
+    fn next_frame(&mut self, frame: &mut Frame) -> bool {
+        let module_count = frame.data.len();
+        let mut step = self.step;
+
+        // all module indices
+        if step == 0 {
+            show_indices(frame);
+            self.step += 1;
+            return true;
+        }
+        step -= 1;
+
+        // one module after another
+        if step < module_count {
+            show_indices(frame);
+            frame.data[step] = index_glyph(step).map(|row| !row);
+            self.step += 1;
+            return true;
+        }
+        step -= module_count;
+
+        // one row after another
+        if step < LED_SQUARE_MATRIX_DIM {
+            for data in &mut frame.data {
+                *data = [0; LED_SQUARE_MATRIX_DIM];
+                data[step] = 0xFF;
+            }
+            self.step += 1;
+            return true;
+        }
+        step -= LED_SQUARE_MATRIX_DIM;
+
+        // one column after another
+        if step < LED_SQUARE_MATRIX_DIM {
+            for data in &mut frame.data {
+                *data = [0b1000_0000 >> step; LED_SQUARE_MATRIX_DIM];
+            }
+            self.step += 1;
+            return true;
+        }
+        step -= LED_SQUARE_MATRIX_DIM;
+
+        if step == 0 {
+            frame.set_data(&[]);
+            self.step += 1;
+            return true;
+        }
+        false
+    }
+}
+
+fn show_indices(frame: &mut Frame) {
+    for (module, data) in frame.data.iter_mut().enumerate() {
+        *data = index_glyph(module);
+    }
+}
+
+fn index_glyph(module: usize) -> SingleDisplayData {
+    // combined chains can have more than 10 modules; they show the last digit of the index
+    core::char::from_digit((module % 10) as u32, 10).map_or([0; LED_SQUARE_MATRIX_DIM], encode_char)
+}
+
+/// Runs the self test. Each step is shown for `ms_step` milliseconds. The displays are prepared
+/// with [`prepare_display`] and the highest intensity first.
+///
+/// * `display` - mutable reference to the display device, e.g. the Max7219 display driver
+/// * `display_count` - count of displays connected to the MAX7219
+/// * `ms_step` - time each step is shown, e.g. `500`
+#[cfg(feature = "std")]
+pub fn self_test<D: MatrixDevice>(
+    display: &mut D,
+    display_count: usize,
+    ms_step: u64,
+) -> Result<(), Error> {
+    run(display, display_count, || {
+        sleep(Duration::from_millis(ms_step))
+    })
+}
+
+/// Like `self_test()` but waits with `delay` after each step. This also works in `no_std`
+/// environments.
+///
+/// * `display` - mutable reference to the display device, e.g. the Max7219 display driver
+/// * `display_count` - count of displays connected to the MAX7219
+/// * `delay` - delay provider, e.g. from the HAL of the microcontroller
+/// * `ms_step` - time each step is shown, e.g. `500`
+pub fn self_test_with_delay<D: MatrixDevice, T: DelayMs<u32>>(
+    display: &mut D,
+    display_count: usize,
+    delay: &mut T,
+    ms_step: u32,
+) -> Result<(), Error> {
+    run(display, display_count, || delay.delay_ms(ms_step))
+}
+
+/// Runs the self test and calls `wait` after each step.
+pub(crate) fn run<D: MatrixDevice>(
+    display: &mut D,
+    display_count: usize,
+    mut wait: impl FnMut(),
+) -> Result<(), Error> {
+    check_display_count_of(display.module_count(), display_count)?;
+    prepare_display(display, display_count, MAX_INTENSITY)?;
+
+    for module in 0..display_count {
+        display.set_display_test(module, true)?;
+    }
+    display.flush()?;
+    wait();
+    for module in 0..display_count {
+        display.set_display_test(module, false)?;
+    }
+
+    play(display, SelfTest::new(), display_count, &mut wait)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::device::MemoryDevice;
+    use crate::recording::RecordingDevice;
+    use crate::MAX_DISPLAYS;
+
+    struct NoDelay;
+
+    impl DelayMs<u32> for NoDelay {
+        fn delay_ms(&mut self, _ms: u32) {}
+    }
+
+    #[test]
+    fn test_self_test_steps() {
+        let mut device = RecordingDevice::new(4);
+        self_test_with_delay(&mut device, 4, &mut NoDelay, 0).unwrap();
+        let frames = device.frames();
+        // prepare, test mode, indices, 4 modules, 8 rows, 8 cols and clear
+        assert_eq!(frames.len(), 1 + 1 + 1 + 4 + 8 + 8 + 1);
+        assert_eq!(frames[1], [[0xFF; 8]; 4]);
+        let indices = ['0', '1', '2', '3'].map(encode_char);
+        assert_eq!(frames[2], indices);
+        assert_eq!(
+            frames[4],
+            [
+                indices[0],
+                indices[1].map(|row| !row),
+                indices[2],
+                indices[3]
+            ]
+        );
+        assert_eq!(frames[7][1], [0xFF, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(frames[15][0], [0b1000_0000; 8]);
+        assert_eq!(frames[23], [[0; 8]; 4]);
+    }
+
+    #[test]
+    fn test_self_test_shows_distinct_indices() {
+        let mut device = RecordingDevice::new(MAX_DISPLAYS);
+        self_test_with_delay(&mut device, MAX_DISPLAYS, &mut NoDelay, 0).unwrap();
+        let indices = &device.frames()[2];
+        for (module, glyph) in indices.iter().enumerate() {
+            assert_ne!(*glyph, [0; 8], "module {} has no index", module);
+            assert!(indices[..module].iter().all(|other| other != glyph));
+        }
+    }
+
+    #[test]
+    fn test_self_test_ends_without_test_mode() {
+        let mut device = MemoryDevice::new(3);
+        self_test_with_delay(&mut device, 3, &mut NoDelay, 0).unwrap();
+        assert!(device.power);
+        assert!(device
+            .modules
+            .iter()
+            .all(|module| !module.display_test && module.intensity == MAX_INTENSITY));
+        assert_eq!(device.rows(), [[0; 8]; 3]);
+        assert!(matches!(
+            self_test_with_delay(&mut device, 0, &mut NoDelay, 0),
+            Err(Error::InvalidDisplayCount(0))
+        ));
+    }
+}
@@ -1,64 +1,211 @@
+use crate::driver::{Driver, PinBus, SharedPin, SpinDelay};
+use crate::{check_display_count, Display, Error};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
 use embedded_hal::digital::v2::OutputPin;
 use gpio_cdev::{Chip, LineHandle, LineRequestFlags};
-use max7219::connectors::PinConnector;
-use max7219::MAX7219;
+
+/// A GPIO chip.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GpioChip {
+    /// The path of the chip, e.g. "/dev/gpiochip0".
+    Path(String),
+    /// The label of the chip, e.g. "pinctrl-bcm2711" on a Raspberry Pi 4. The numbering of the
+    /// chips differs between models, the label doesn't. See the output of `gpiodetect`.
+    Label(String),
+}
+
+/// A GPIO line of a chip.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GpioLine {
+    /// The offset of the line on the chip, i.e. the GPIO pin number on a Raspberry Pi.
+    Offset(u32),
+    /// The name of the line, e.g. "GPIO12" on a Raspberry Pi. See the output of `gpioinfo`.
+    Name(String),
+}
 
 /// The type of the driver.
-pub type Max7219 = MAX7219<PinConnector<LHandle, LHandle, LHandle>>;
+pub type Max7219 = Driver<PinBus<LHandle, LHandle, LHandle, SpinDelay>>;
 
-// setup glue structure between crates "embedded_hal", "gpio_cdev" and "max7219 "
+/// The type of the driver of a chain that shares the data and clock pins with other chains.
+pub type Max7219Chain = Driver<PinBus<SharedPin<LHandle>, LHandle, SharedPin<LHandle>, SpinDelay>>;
+
+// setup glue structure between crates "embedded_hal" and "gpio_cdev"
 /// Wrapper around [`gpio_cdev::LineHandle`] that implements [`embedded_hal::digital::v2::OutputPin`].
 #[derive(Debug)]
 pub struct LHandle(LineHandle);
 impl OutputPin for LHandle {
-    type Error = ();
+    type Error = gpio_cdev::Error;
 
     fn set_low(&mut self) -> Result<(), Self::Error> {
-        self.0.set_value(0).map_err(|_| ())
+        self.0.set_value(0)
     }
 
     fn set_high(&mut self) -> Result<(), Self::Error> {
-        self.0.set_value(1).map_err(|_| ())
+        self.0.set_value(1)
     }
 }
 // setup glue structure done
 
 /// Set's up the MAX7219-driver using "gpio_cdev". This only works on Linux. It works for example
 /// on Raspberry. The communication uses SPI protocol. This does not require the "SPI Module" on
-/// Raspberry Pi to be activated. This works via regular GPIO pins.
+/// Raspberry Pi to be activated. This works via regular GPIO pins. The returned [`Display`] keeps
+/// the count of displays. [`crate::DisplayConfig`] is more convenient and also prepares the
+/// displays.
+///
+/// The pins are toggled as fast as possible. If the modules don't latch the data reliably, e.g.
+/// with long wires on a Raspberry Pi 4 or 5, set a minimum clock half-period with
+/// `DisplayConfig::clock_half_period_us()` or [`crate::driver::PinBus::set_half_period_us`].
 ///
 /// * `gpio_dev` the gpio device. Probably "/dev/gpiochip0"
-/// * `num_displays` number of displays
+/// * `num_displays` number of displays; between 1 and [`crate::MAX_DISPLAYS`]
 /// * `data_pin` number of GPIO pin used as data pin
-/// * `clk_pin` number of GPIO pin used as clock pin
 /// * `cs_pin` number of GPIO pin used as cs (chip select) pin
+/// * `clk_pin` number of GPIO pin used as clock pin
 pub fn setup(
     gpio_dev: &str,
     num_displays: usize,
     data_pin: u32,
     cs_pin: u32,
     clk_pin: u32,
-) -> Max7219 {
-    let mut gpio = Chip::new(gpio_dev).unwrap();
-    let data_pin = gpio
-        .get_line(data_pin)
-        .unwrap()
-        .request(LineRequestFlags::OUTPUT, 0, "spi-data-pin")
-        .unwrap();
-    let cs_pin = gpio
-        .get_line(cs_pin)
-        .unwrap()
-        .request(LineRequestFlags::OUTPUT, 0, "spi-cs-pin")
-        .unwrap();
-    let clk_pin = gpio
-        .get_line(clk_pin)
-        .unwrap()
-        .request(LineRequestFlags::OUTPUT, 0, "spi-clk-pin")
-        .unwrap();
-
-    let data_pin = LHandle(data_pin);
-    let clk_pin = LHandle(clk_pin);
-    let cs_pin = LHandle(cs_pin);
-
-    MAX7219::from_pins(num_displays, data_pin, cs_pin, clk_pin).unwrap()
+) -> Result<Display<Max7219>, Error> {
+    setup_lines(
+        &GpioChip::Path(gpio_dev.to_string()),
+        num_displays,
+        &GpioLine::Offset(data_pin),
+        &GpioLine::Offset(cs_pin),
+        &GpioLine::Offset(clk_pin),
+        0,
+    )
+}
+
+/// Like [`setup`] but the chip and the lines can also be given by their label and name.
+pub(crate) fn setup_lines(
+    chip: &GpioChip,
+    num_displays: usize,
+    data_pin: &GpioLine,
+    cs_pin: &GpioLine,
+    clk_pin: &GpioLine,
+    half_period_us: u32,
+) -> Result<Display<Max7219>, Error> {
+    check_display_count(num_displays)?;
+    let mut gpio = open_chip(chip)?;
+    let data_pin = request_output(&mut gpio, data_pin, "spi-data-pin", 0)?;
+    let cs_pin = request_output(&mut gpio, cs_pin, "spi-cs-pin", 1)?;
+    let clk_pin = request_output(&mut gpio, clk_pin, "spi-clk-pin", 0)?;
+
+    let bus = PinBus::with_delay(data_pin, cs_pin, clk_pin, SpinDelay, half_period_us);
+    let driver = Driver::new(bus, num_displays)?;
+    Display::new(driver, num_displays)
+}
+
+/// Opens the chip at the given path or the first chip with the given label.
+fn open_chip(chip: &GpioChip) -> Result<Chip, Error> {
+    match chip {
+        GpioChip::Path(path) => Chip::new(path).map_err(Error::GpioOpen),
+        GpioChip::Label(label) => {
+            for gpio in gpio_cdev::chips().map_err(Error::GpioOpen)? {
+                let gpio = gpio.map_err(Error::GpioOpen)?;
+                if gpio.label() == label {
+                    return Ok(gpio);
+                }
+            }
+            Err(Error::ChipNotFound(label.clone()))
+        }
+    }
+}
+
+/// Returns the offset of the line on the chip.
+fn line_offset(gpio: &Chip, line: &GpioLine) -> Result<u32, Error> {
+    match line {
+        GpioLine::Offset(offset) => Ok(*offset),
+        GpioLine::Name(name) => {
+            for candidate in gpio.lines() {
+                let offset = candidate.offset();
+                let info = candidate
+                    .info()
+                    .map_err(|error| Error::LineRequest(offset, error))?;
+                if info.name() == Some(name.as_str()) {
+                    return Ok(offset);
+                }
+            }
+            Err(Error::LineNotFound(name.clone()))
+        }
+    }
+}
+
+/// Set's up several independent chains that share the data and clock pins but have their own
+/// cs (chip select) pin, e.g. two signs on one Raspberry Pi. Like [`setup`], this works via
+/// regular GPIO pins. The displays can be used on their own or as one display with
+/// [`crate::device::Combined`]. They must be used from the same thread.
+///
+/// * `gpio_dev` the gpio device. Probably "/dev/gpiochip0"
+/// * `data_pin` number of GPIO pin used as data pin by all chains
+/// * `clk_pin` number of GPIO pin used as clock pin by all chains
+/// * `chains` number of the GPIO pin used as cs pin and number of displays of each chain
+/// * `half_period_us` minimum time in microseconds the clock pin is high or low; `0` toggles the
+///   pins as fast as possible. See `DisplayConfig::clock_half_period_us()`.
+pub fn setup_chains(
+    gpio_dev: &str,
+    data_pin: u32,
+    clk_pin: u32,
+    chains: &[(u32, usize)],
+    half_period_us: u32,
+) -> Result<Vec<Display<Max7219Chain>>, Error> {
+    for (_, num_displays) in chains {
+        check_display_count(*num_displays)?;
+    }
+    let mut gpio = open_chip(&GpioChip::Path(gpio_dev.to_string()))?;
+    let data_pin = SharedPin::new(request_output(
+        &mut gpio,
+        &GpioLine::Offset(data_pin),
+        "spi-data-pin",
+        0,
+    )?);
+    let clk_pin = SharedPin::new(request_output(
+        &mut gpio,
+        &GpioLine::Offset(clk_pin),
+        "spi-clk-pin",
+        0,
+    )?);
+
+    // all cs pins must be high before the first transfer, so that no chain latches the data of
+    // another chain; they start high, so no chain is selected in between
+    let mut cs_pins = Vec::new();
+    for (offset, _) in chains {
+        cs_pins.push(request_output(
+            &mut gpio,
+            &GpioLine::Offset(*offset),
+            "spi-cs-pin",
+            1,
+        )?);
+    }
+
+    let mut displays = Vec::new();
+    for (cs_pin, (_, num_displays)) in cs_pins.into_iter().zip(chains) {
+        let bus = PinBus::with_delay(
+            data_pin.clone(),
+            cs_pin,
+            clk_pin.clone(),
+            SpinDelay,
+            half_period_us,
+        );
+        let driver = Driver::new(bus, *num_displays)?;
+        displays.push(Display::new(driver, *num_displays)?);
+    }
+    Ok(displays)
+}
+
+/// Requests the GPIO line as output with the initial value `default`.
+fn request_output(
+    gpio: &mut Chip,
+    line: &GpioLine,
+    consumer: &str,
+    default: u8,
+) -> Result<LHandle, Error> {
+    let line = line_offset(gpio, line)?;
+    gpio.get_line(line)
+        .and_then(|handle| handle.request(LineRequestFlags::OUTPUT, default, consumer))
+        .map(LHandle)
+        .map_err(|error| Error::LineRequest(line, error))
 }
@@ -0,0 +1,344 @@
+//! Support for 8-digit 7-segment displays, which are driven by the same MAX7219 as the matrix
+//! displays. The setup is shared: a [`Display`] from `DisplayConfig::build()` or
+//! [`Display::new`] is turned into a [`SevenSegment`] display.
+//!
+//! Digit 0 is the rightmost digit of a module. Boards with fewer digits, like 4- or 6-digit
+//! displays, set the scan limit with [`Display::set_scan_limit`] before; the text is then
+//! aligned to the digits that are shown. In raw mode, each digit is a byte with the
+//! segments `DP A B C D E F G` from the most to the least significant bit.
+
+use crate::brightness::scan_limit_intensity;
+use crate::device::MatrixDevice;
+use crate::mappings::SingleDisplayData;
+use crate::{Display, Error, LED_SQUARE_MATRIX_DIM};
+use alloc::vec::Vec;
+use max7219::DecodeMode;
+
+/// Count of digits of a module.
+pub const DIGITS: usize = LED_SQUARE_MATRIX_DIM;
+
+/// Bit of the decimal point in both modes.
+pub const DECIMAL_POINT: u8 = 0b1000_0000;
+
+/// How the digits are encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SegmentMode {
+    /// The MAX7219 decodes the digits with its Code-B font, which only has `0-9`, `-`, `E`, `H`,
+    /// `L`, `P` and the blank. See [`code_b`].
+    CodeB,
+    /// The segments are set directly, which allows more characters. See [`segments`].
+    Raw,
+}
+
+impl SegmentMode {
+    /// Encodes a character; `None` if the mode can't show it.
+    pub const fn encode(self, c: char) -> Option<u8> {
+        match self {
+            Self::CodeB => code_b(c),
+            Self::Raw => segments(c),
+        }
+    }
+
+    /// The encoding of a digit with all segments off.
+    pub const fn blank(self) -> u8 {
+        match self {
+            Self::CodeB => 0x0F,
+            Self::Raw => 0,
+        }
+    }
+
+    const fn decode_mode(self) -> DecodeMode {
+        match self {
+            Self::CodeB => DecodeMode::CodeBDigits7_0,
+            Self::Raw => DecodeMode::NoDecode,
+        }
+    }
+}
+
+/// The Code-B value of a character, without the decimal point.
+pub const fn code_b(c: char) -> Option<u8> {
+    match c {
+        '0'..='9' => Some(c as u8 - b'0'),
+        '-' => Some(0x0A),
+        'E' | 'e' => Some(0x0B),
+        'H' | 'h' => Some(0x0C),
+        'L' | 'l' => Some(0x0D),
+        'P' | 'p' => Some(0x0E),
+        ' ' => Some(0x0F),
+        _ => None,
+    }
+}
+
+/// The segments of a character for the raw mode, without the decimal point. Letters that can't
+/// be shown on 7 segments, like `K`, `M`, `V`, `W`, `X` and `Z`, are `None`.
+pub const fn segments(c: char) -> Option<u8> {
+    let segments = match c {
+        '0' | 'O' => 0x7E,
+        '1' => 0x30,
+        '2' => 0x6D,
+        '3' => 0x79,
+        '4' => 0x33,
+        '5' | 'S' | 's' => 0x5B,
+        '6' => 0x5F,
+        '7' => 0x70,
+        '8' => 0x7F,
+        '9' | 'g' => 0x7B,
+        'A' | 'a' => 0x77,
+        // `B` and `D` use the lower case glyphs, the upper case ones look like `8` and `0`
+        'B' | 'b' => 0x1F,
+        'C' => 0x4E,
+        'c' => 0x0D,
+        'D' | 'd' => 0x3D,
+        'E' | 'e' => 0x4F,
+        'F' | 'f' => 0x47,
+        'G' => 0x5E,
+        'H' => 0x37,
+        'h' => 0x17,
+        'I' | 'i' => 0x06,
+        'J' | 'j' => 0x3C,
+        'L' | 'l' => 0x0E,
+        'N' | 'n' => 0x15,
+        'o' => 0x1D,
+        'P' | 'p' => 0x67,
+        'q' | 'Q' => 0x73,
+        'R' | 'r' => 0x05,
+        'T' | 't' => 0x0F,
+        'U' => 0x3E,
+        'u' => 0x1C,
+        'Y' | 'y' => 0x3B,
+        '-' => 0x01,
+        '_' => 0x08,
+        '=' => 0x09,
+        '°' => 0x63,
+        ' ' => 0x00,
+        _ => return None,
+    };
+    Some(segments)
+}
+
+/// Encodes `text` for a module with `digit_count` digits in the given mode. The text starts at
+/// the leftmost digit; a `.` sets the decimal point of the previous character and doesn't need a
+/// digit of its own.
+///
+/// Fails with [`Error::UnsupportedChar`] if the mode can't show a character and with
+/// [`Error::TextTooLong`] if the text needs more than `digit_count` digits.
+pub fn encode_text(
+    text: &str,
+    mode: SegmentMode,
+    digit_count: usize,
+) -> Result<SingleDisplayData, Error> {
+    let digit_count = digit_count.min(DIGITS);
+    let mut digits = [mode.blank(); DIGITS];
+    // count of used digits from the left
+    let mut used = 0;
+    let mut previous_has_point = true;
+    for c in text.chars() {
+        if c == '.' && !previous_has_point {
+            digits[digit_count - used] |= DECIMAL_POINT;
+            previous_has_point = true;
+            continue;
+        }
+        if used == digit_count {
+            return Err(Error::TextTooLong(text.chars().count()));
+        }
+        digits[digit_count - 1 - used] = if c == '.' {
+            mode.blank() | DECIMAL_POINT
+        } else {
+            mode.encode(c).ok_or(Error::UnsupportedChar(c))?
+        };
+        previous_has_point = c == '.';
+        used += 1;
+    }
+    Ok(digits)
+}
+
+/// A chain of 7-segment displays with up to 8 digits each.
+#[derive(Debug)]
+pub struct SevenSegment<D: MatrixDevice> {
+    display: Display<D>,
+    mode: SegmentMode,
+    /// The digits of each module, as written to the device.
+    digits: Vec<SingleDisplayData>,
+}
+
+impl<D: MatrixDevice> SevenSegment<D> {
+    /// Turns on the displays, sets the decode mode and the brightness and clears all digits.
+    /// The count of digits is taken from the scan limit of the display, and the intensity is
+    /// reduced for it like by [`scan_limit_intensity`].
+    ///
+    /// * `display` - the display, e.g. from `DisplayConfig::build()`
+    /// * `mode` - how the digits are encoded
+    /// * `intensity` - brightness for the display; value between `0x00` and `0x0F`
+    pub fn new(mut display: Display<D>, mode: SegmentMode, intensity: u8) -> Result<Self, Error> {
+        display.prepare(scan_limit_intensity(intensity, display.scan_limit()))?;
+        let display_count = display.display_count();
+        let mut seven_segment = Self {
+            display,
+            mode,
+            digits: vec![[mode.blank(); DIGITS]; display_count],
+        };
+        for module in 0..display_count {
+            let device = seven_segment.display.device_mut();
+            device.set_decode_mode(module, mode.decode_mode())?;
+            seven_segment.clear(module)?;
+        }
+        Ok(seven_segment)
+    }
+
+    /// The mode of the digits.
+    pub fn mode(&self) -> SegmentMode {
+        self.mode
+    }
+
+    /// The underlying display, e.g. to change the brightness.
+    pub fn display_mut(&mut self) -> &mut Display<D> {
+        &mut self.display
+    }
+
+    /// Returns the underlying display.
+    pub fn into_inner(self) -> Display<D> {
+        self.display
+    }
+
+    /// Turns all digits of the module off.
+    pub fn clear(&mut self, module: usize) -> Result<(), Error> {
+        self.write_digits(module, &[self.mode.blank(); DIGITS])
+    }
+
+    /// Writes the encoded digits of the module; index 0 is the rightmost digit. The encoding
+    /// depends on the [`SegmentMode`].
+    pub fn write_digits(&mut self, module: usize, digits: &SingleDisplayData) -> Result<(), Error> {
+        let stored = self
+            .digits
+            .get_mut(module)
+            .ok_or(Error::InvalidModule(module))?;
+        *stored = *digits;
+        let device = self.display.device_mut();
+        device.write_rows(module, digits)?;
+        device.flush()
+    }
+
+    /// Count of digits of each module, i.e. the scan limit of the display plus one.
+    pub fn digit_count(&self) -> usize {
+        usize::from(self.display.scan_limit()) + 1
+    }
+
+    /// Shows `text` on the module, starting at the leftmost digit. See [`encode_text`].
+    pub fn write_str(&mut self, module: usize, text: &str) -> Result<(), Error> {
+        let digits = encode_text(text, self.mode, self.digit_count())?;
+        self.write_digits(module, &digits)
+    }
+
+    /// Shows the number right-aligned on the module.
+    pub fn write_number(&mut self, module: usize, number: i64) -> Result<(), Error> {
+        let width = self.digit_count();
+        self.write_str(module, &format!("{:>width$}", number, width = width))
+    }
+
+    /// Shows the number as upper case hexadecimal right-aligned on the module. This needs
+    /// [`SegmentMode::Raw`] for the digits `A` to `F`.
+    pub fn write_hex(&mut self, module: usize, number: u32) -> Result<(), Error> {
+        let width = self.digit_count();
+        self.write_str(module, &format!("{:>width$X}", number, width = width))
+    }
+
+    /// Turns the decimal point of a digit on or off; digit 0 is the rightmost digit.
+    pub fn set_decimal_point(
+        &mut self,
+        module: usize,
+        digit: usize,
+        on: bool,
+    ) -> Result<(), Error> {
+        if digit >= self.digit_count() {
+            return Err(Error::InvalidConfig("the digit is not shown"));
+        }
+        let mut digits = *self
+            .digits
+            .get(module)
+            .ok_or(Error::InvalidModule(module))?;
+        if on {
+            digits[digit] |= DECIMAL_POINT;
+        } else {
+            digits[digit] &= !DECIMAL_POINT;
+        }
+        self.write_digits(module, &digits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::device::MemoryDevice;
+
+    #[test]
+    fn test_encode_text() {
+        assert_eq!(
+            encode_text("1.5-E", SegmentMode::CodeB, DIGITS).unwrap(),
+            [0x0F, 0x0F, 0x0F, 0x0F, 0x0B, 0x0A, 0x05, 0x81]
+        );
+        assert_eq!(
+            encode_text("..A", SegmentMode::Raw, DIGITS).unwrap(),
+            [0, 0, 0, 0, 0, 0x77, 0x80, 0x80]
+        );
+        assert!(matches!(
+            encode_text("A", SegmentMode::CodeB, DIGITS),
+            Err(Error::UnsupportedChar('A'))
+        ));
+        assert!(matches!(
+            encode_text("123456789", SegmentMode::CodeB, DIGITS),
+            Err(Error::TextTooLong(9))
+        ));
+        // the decimal points don't need digits of their own
+        assert!(encode_text("1.2.3.4.5.6.7.8.", SegmentMode::CodeB, DIGITS).is_ok());
+        // a 4-digit board
+        assert_eq!(
+            encode_text("12", SegmentMode::CodeB, 4).unwrap(),
+            [0x0F, 0x0F, 0x02, 0x01, 0x0F, 0x0F, 0x0F, 0x0F]
+        );
+        assert!(matches!(
+            encode_text("12345", SegmentMode::CodeB, 4),
+            Err(Error::TextTooLong(5))
+        ));
+    }
+
+    #[test]
+    fn test_seven_segment_writes() {
+        let display = Display::new(MemoryDevice::new(2), 2).unwrap();
+        let mut seven_segment = SevenSegment::new(display, SegmentMode::Raw, 0x08).unwrap();
+        seven_segment.write_number(0, -42).unwrap();
+        seven_segment.write_hex(1, 0xBEEF).unwrap();
+        seven_segment.set_decimal_point(1, 0, true).unwrap();
+
+        let device = seven_segment.into_inner().into_inner();
+        assert_eq!(device.modules[0].rows, [0x6D, 0x33, 0x01, 0, 0, 0, 0, 0]);
+        assert_eq!(
+            device.modules[1].rows[..4],
+            [0x47 | DECIMAL_POINT, 0x4F, 0x4F, 0x1F]
+        );
+        assert_eq!(device.modules[1].intensity, 0x08);
+    }
+
+    #[test]
+    fn test_seven_segment_scan_limit() {
+        let mut display = Display::new(MemoryDevice::new(1), 1).unwrap();
+        display.set_scan_limit(3).unwrap();
+        let mut seven_segment = SevenSegment::new(display, SegmentMode::CodeB, 0x08).unwrap();
+        assert_eq!(seven_segment.digit_count(), 4);
+        seven_segment.write_number(0, 42).unwrap();
+        assert!(seven_segment.write_number(0, 12345).is_err());
+        assert!(seven_segment.set_decimal_point(0, 4, true).is_err());
+
+        let device = seven_segment.into_inner().into_inner();
+        assert_eq!(device.modules[0].scan_limit, 3);
+        assert_eq!(device.modules[0].rows[..4], [0x02, 0x04, 0x0F, 0x0F]);
+    }
+
+    #[test]
+    fn test_seven_segment_intensity_for_scan_limit() {
+        let mut display = Display::new(MemoryDevice::new(2), 2).unwrap();
+        display.set_scan_limit(3).unwrap();
+        let seven_segment = SevenSegment::new(display, SegmentMode::CodeB, 0x0F).unwrap();
+        let device = seven_segment.into_inner().into_inner();
+        assert!(device.modules.iter().all(|module| module.intensity == 0x07));
+    }
+}
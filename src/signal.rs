@@ -0,0 +1,47 @@
+use crate::Error;
+use signal_hook::consts::{SIGINT, SIGTERM};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+/// Returns a flag that is set as soon as the process receives SIGINT or SIGTERM. Instead of
+/// being terminated, the process can stop its animations (see
+/// [`crate::animation::Animation::until`]) and shut the displays down.
+///
+/// ```no_run
+/// use max_7219_led_matrix_util::animation::{Animation, Scroller};
+/// use max_7219_led_matrix_util::{stop_on_signal, DisplayConfig};
+///
+/// let stop = stop_on_signal().unwrap();
+/// let mut display = DisplayConfig::new(4)
+///     .data_pin(12)
+///     .cs_pin(16)
+///     .clk_pin(21)
+///     .build()
+///     .unwrap()
+///     .shutdown_on_drop();
+/// display
+///     .play_animation(Scroller::new("HELLO", None).until(&stop), 50)
+///     .unwrap();
+/// // the display is cleared and powered off when it is dropped
+/// ```
+pub fn stop_on_signal() -> Result<Arc<AtomicBool>, Error> {
+    let stop = Arc::new(AtomicBool::new(false));
+    for signal in [SIGINT, SIGTERM] {
+        signal_hook::flag::register(signal, Arc::clone(&stop))?;
+    }
+    Ok(stop)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::Ordering;
+
+    #[test]
+    fn test_stop_on_signal() {
+        let stop = stop_on_signal().unwrap();
+        assert!(!stop.load(Ordering::Relaxed));
+        signal_hook::low_level::raise(SIGTERM).unwrap();
+        assert!(stop.load(Ordering::Relaxed));
+    }
+}
@@ -0,0 +1,51 @@
+use crate::driver::{Driver, SpiBus};
+use crate::{check_display_count, Display, Error};
+use embedded_hal::blocking::spi::Write;
+use spidev::{SpiModeFlags, Spidev, SpidevOptions};
+use std::io;
+
+/// The type of the driver, if hardware SPI is used.
+pub type Max7219Spi = Driver<SpiBus<SpiDev>>;
+
+/// Maximum SPI clock frequency supported by the MAX7219.
+pub const MAX_SPEED_HZ: u32 = 10_000_000;
+
+// setup glue structure between crates "embedded_hal" and "spidev"
+/// Wrapper around [`spidev::Spidev`] that implements [`embedded_hal::blocking::spi::Write`].
+#[derive(Debug)]
+pub struct SpiDev(Spidev);
+impl Write<u8> for SpiDev {
+    type Error = io::Error;
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+        io::Write::write_all(&mut self.0, words)
+    }
+}
+// setup glue structure done
+
+/// Set's up the MAX7219-driver using the hardware SPI of Linux via "spidev". This is much faster
+/// and needs less CPU time than [`crate::setup_adapter`], which bit-bangs SPI over GPIO pins.
+/// On Raspberry Pi, the "SPI Module" must be activated (e.g. via `raspi-config`). Connect
+/// DIN to MOSI, CLK to SCLK and CS to CE0 (for "/dev/spidev0.0") or CE1 (for "/dev/spidev0.1").
+/// The returned [`Display`] keeps the count of displays.
+///
+/// * `spi_dev` the spi device. Probably "/dev/spidev0.0"
+/// * `num_displays` number of displays; between 1 and [`crate::MAX_DISPLAYS`]
+/// * `max_speed_hz` SPI clock frequency; at most [`MAX_SPEED_HZ`]
+pub fn setup(
+    spi_dev: &str,
+    num_displays: usize,
+    max_speed_hz: u32,
+) -> Result<Display<Max7219Spi>, Error> {
+    check_display_count(num_displays)?;
+    let mut spi = Spidev::open(spi_dev)?;
+    let options = SpidevOptions::new()
+        .bits_per_word(8)
+        .max_speed_hz(max_speed_hz.min(MAX_SPEED_HZ))
+        .mode(SpiModeFlags::SPI_MODE_0)
+        .build();
+    spi.configure(&options)?;
+
+    let driver = Driver::new(SpiBus(SpiDev(spi)), num_displays)?;
+    Display::new(driver, num_displays)
+}
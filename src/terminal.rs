@@ -0,0 +1,172 @@
+//! Simulator that renders the display chain to the terminal. Useful for development without
+//! the hardware. Every function that works with a [`MatrixDevice`], like
+//! [`crate::show_moving_text_in_loop`], also works with a [`TerminalDevice`].
+
+use crate::device::{MatrixDevice, MemoryDevice};
+use crate::mappings::SingleDisplayData;
+use crate::{Error, LED_SQUARE_MATRIX_DIM};
+use alloc::string::String;
+use max7219::DecodeMode;
+use std::io::{self, Stdout, Write};
+
+/// How the pixels are rendered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TerminalStyle {
+    /// Lit pixels are drawn with Unicode block characters, other pixels are empty.
+    Blocks,
+    /// Pixels are drawn as colored blocks with ANSI escape sequences. Lit pixels are red, the
+    /// brightness depends on the intensity. Other pixels are dark grey.
+    Ansi,
+}
+
+/// A [`MatrixDevice`] that renders the display chain to a terminal. The output is redrawn in
+/// place after each frame, i.e. when [`MatrixDevice::flush`] is called.
+#[derive(Debug)]
+pub struct TerminalDevice<W: Write = Stdout> {
+    state: MemoryDevice,
+    out: W,
+    style: TerminalStyle,
+    /// Whether the chain was drawn before. If so, the cursor is moved up before the next draw.
+    drawn: bool,
+}
+
+impl TerminalDevice<Stdout> {
+    /// Creates a simulator for `module_count` displays that renders to stdout.
+    pub fn new(module_count: usize, style: TerminalStyle) -> Self {
+        Self::with_writer(module_count, style, io::stdout())
+    }
+}
+
+impl<W: Write> TerminalDevice<W> {
+    /// Creates a simulator for `module_count` displays that renders to `out`.
+    pub fn with_writer(module_count: usize, style: TerminalStyle, out: W) -> Self {
+        Self {
+            state: MemoryDevice::new(module_count),
+            out,
+            style,
+            drawn: false,
+        }
+    }
+
+    /// The simulated state of the displays.
+    pub fn state(&self) -> &MemoryDevice {
+        &self.state
+    }
+
+    /// Returns the writer the output is rendered to.
+    pub fn into_writer(self) -> W {
+        self.out
+    }
+
+    /// Renders the whole chain as text with one line per row.
+    pub fn render(&self) -> String {
+        let mut output = String::new();
+        for y in 0..LED_SQUARE_MATRIX_DIM {
+            for (i, module) in self.state.modules.iter().enumerate() {
+                if i > 0 {
+                    output.push(' ');
+                }
+                for x in 0..LED_SQUARE_MATRIX_DIM {
+                    // the test mode lights all LEDs with the highest intensity
+                    let on = module.display_test
+                        || (self.state.power
+                            && y <= usize::from(module.scan_limit)
+                            && module.rows[y] & (0b1000_0000 >> x) != 0);
+                    let intensity = if module.display_test {
+                        0x0F
+                    } else {
+                        module.intensity
+                    };
+                    self.push_pixel(&mut output, on, intensity);
+                }
+            }
+            if self.style == TerminalStyle::Ansi {
+                output.push_str("\x1b[0m");
+            }
+            output.push('\n');
+        }
+        output
+    }
+
+    fn push_pixel(&self, output: &mut String, on: bool, intensity: u8) {
+        match self.style {
+            TerminalStyle::Blocks => output.push_str(if on { "██" } else { "  " }),
+            TerminalStyle::Ansi => {
+                // dark to bright red of the 256 color palette
+                const REDS: [u8; 5] = [52, 88, 124, 160, 196];
+                let color = if on {
+                    REDS[usize::from(intensity.min(0x0F)) * REDS.len() / 16]
+                } else {
+                    236
+                };
+                output.push_str(&format!("\x1b[38;5;{}m██", color));
+            }
+        }
+    }
+}
+
+impl<W: Write> MatrixDevice for TerminalDevice<W> {
+    fn write_rows(&mut self, module: usize, rows: &SingleDisplayData) -> Result<(), Error> {
+        self.state.write_rows(module, rows)
+    }
+
+    fn set_intensity(&mut self, module: usize, intensity: u8) -> Result<(), Error> {
+        self.state.set_intensity(module, intensity)
+    }
+
+    fn power_on(&mut self) -> Result<(), Error> {
+        self.state.power_on()
+    }
+
+    fn power_off(&mut self) -> Result<(), Error> {
+        self.state.power_off()
+    }
+
+    fn set_scan_limit(&mut self, module: usize, limit: u8) -> Result<(), Error> {
+        self.state.set_scan_limit(module, limit)
+    }
+
+    fn set_decode_mode(&mut self, module: usize, mode: DecodeMode) -> Result<(), Error> {
+        self.state.set_decode_mode(module, mode)
+    }
+
+    fn set_display_test(&mut self, module: usize, on: bool) -> Result<(), Error> {
+        self.state.set_display_test(module, on)
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        let output = self.render();
+        if self.drawn {
+            // move the cursor up to the first row
+            write!(self.out, "\x1b[{}A", LED_SQUARE_MATRIX_DIM)?;
+        }
+        self.out.write_all(output.as_bytes())?;
+        self.out.flush()?;
+        self.drawn = true;
+        Ok(())
+    }
+
+    fn module_count(&self) -> Option<usize> {
+        self.state.module_count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prepare_display;
+    use std::vec::Vec;
+
+    #[test]
+    fn test_render_blocks() {
+        let mut device = TerminalDevice::with_writer(2, TerminalStyle::Blocks, Vec::new());
+        prepare_display(&mut device, 2, 0x0F).unwrap();
+        device.write_rows(1, &[0b1000_0001; 8]).unwrap();
+        device.flush().unwrap();
+        let output = String::from_utf8(device.into_writer()).unwrap();
+        let empty = format!("{} {}\n", " ".repeat(16), " ".repeat(16)).repeat(8);
+        let lit = format!("{} ██{}██\n", " ".repeat(16), " ".repeat(12)).repeat(8);
+        // the second draw moves the cursor up first
+        assert_eq!(output, format!("{}\x1b[8A{}", empty, lit));
+    }
+}
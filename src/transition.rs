@@ -0,0 +1,260 @@
+//! Transition effects between two frames, e.g. between two messages on a sign.
+//! A [`Transition`] is an [`Animation`] and can be chained with other animations, like the
+//! [`crate::animation::Scroller`].
+
+use crate::animation::{pixel, set_pixel, Animation, Frame};
+use crate::brightness::gamma_intensity;
+use crate::mappings::SingleDisplayData;
+use crate::LED_SQUARE_MATRIX_DIM;
+use alloc::vec::Vec;
+
+/// Direction in which a transition moves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Towards the first (leftmost) col.
+    Left,
+    /// Towards the last (rightmost) col.
+    Right,
+    /// Towards the first row.
+    Up,
+    /// Towards the last row.
+    Down,
+}
+
+/// The kind of a [`Transition`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransitionKind {
+    /// The new content pushes the old content out of the display in the given direction.
+    Push(Direction),
+    /// The new content is drawn over the old content with an edge moving in the given direction.
+    Wipe(Direction),
+    /// The pixels switch to the new content in a random order. The value is the seed for the
+    /// (deterministic) pseudo random order.
+    Dissolve(u32),
+    /// The new content is revealed from the center to the edges.
+    CurtainOpen,
+    /// The new content is revealed from the edges to the center.
+    CurtainClose,
+    /// The old content fades out and the new content fades in via the intensity register.
+    /// The value is the maximum brightness (`0x00` to `0x0F`).
+    Fade(u8),
+}
+
+/// Transition from the content on the display to a new content. Takes exactly `duration` frames.
+/// The last frame shows the new content.
+#[derive(Debug, Clone)]
+pub struct Transition {
+    kind: TransitionKind,
+    duration: usize,
+    step: usize,
+    from: Option<Vec<SingleDisplayData>>,
+    to: Vec<SingleDisplayData>,
+}
+
+impl Transition {
+    /// Creates a new transition to `to`. The transition starts from the content of the frame
+    /// at the first step, i.e. the last frame of the previous animation. Use [`Self::from`] to
+    /// set the start content explicitly.
+    ///
+    /// * `kind` - the effect
+    /// * `duration` - duration in frames
+    /// * `to` - the new content
+    pub const fn new(kind: TransitionKind, duration: usize, to: Vec<SingleDisplayData>) -> Self {
+        Self {
+            kind,
+            duration,
+            step: 0,
+            from: None,
+            to,
+        }
+    }
+
+    /// Sets the content the transition starts from.
+    pub fn from(mut self, from: Vec<SingleDisplayData>) -> Self {
+        self.from = Some(from);
+        self
+    }
+
+    /// Renders the current step into `frame`.
+    fn render(&self, from: &[SingleDisplayData], frame: &mut Frame) {
+        let width = frame.width();
+        let height = LED_SQUARE_MATRIX_DIM;
+        let step = self.step;
+        let duration = self.duration;
+        // progress in cols/rows
+        let col_offset = width * step / duration;
+        let row_offset = height * step / duration;
+
+        if let TransitionKind::Fade(max_intensity) = self.kind {
+            let half = duration / 2;
+            let (data, intensity) = if step <= half {
+                (from, gamma_intensity(max_intensity, 0, step, half))
+            } else {
+                (
+                    self.to.as_slice(),
+                    gamma_intensity(0, max_intensity, step - half, duration - half),
+                )
+            };
+            for x in 0..width {
+                for y in 0..height {
+                    set_pixel(&mut frame.data, x, y, pixel(data, x, y));
+                }
+            }
+            frame.set_intensity(intensity);
+            return;
+        }
+
+        for x in 0..width {
+            for y in 0..height {
+                let on = match self.kind {
+                    TransitionKind::Push(Direction::Left) => {
+                        if x + col_offset < width {
+                            pixel(from, x + col_offset, y)
+                        } else {
+                            pixel(&self.to, x + col_offset - width, y)
+                        }
+                    }
+                    TransitionKind::Push(Direction::Right) => {
+                        if x >= col_offset {
+                            pixel(from, x - col_offset, y)
+                        } else {
+                            pixel(&self.to, x + width - col_offset, y)
+                        }
+                    }
+                    TransitionKind::Push(Direction::Up) => {
+                        if y + row_offset < height {
+                            pixel(from, x, y + row_offset)
+                        } else {
+                            pixel(&self.to, x, y + row_offset - height)
+                        }
+                    }
+                    TransitionKind::Push(Direction::Down) => {
+                        if y >= row_offset {
+                            pixel(from, x, y - row_offset)
+                        } else {
+                            pixel(&self.to, x, y + height - row_offset)
+                        }
+                    }
+                    TransitionKind::Wipe(direction) => {
+                        let new = match direction {
+                            Direction::Left => x >= width - col_offset,
+                            Direction::Right => x < col_offset,
+                            Direction::Up => y >= height - row_offset,
+                            Direction::Down => y < row_offset,
+                        };
+                        pixel(if new { &self.to } else { from }, x, y)
+                    }
+                    TransitionKind::Dissolve(seed) => {
+                        let threshold = scramble((x * height + y) as u32 ^ seed) as usize;
+                        let new = threshold % duration < step;
+                        pixel(if new { &self.to } else { from }, x, y)
+                    }
+                    TransitionKind::CurtainOpen => {
+                        // distance to the center in half cols
+                        let distance = if 2 * x + 1 > width {
+                            2 * x + 1 - width
+                        } else {
+                            width - 2 * x - 1
+                        };
+                        let new = distance < col_offset;
+                        pixel(if new { &self.to } else { from }, x, y)
+                    }
+                    TransitionKind::CurtainClose => {
+                        let distance = x.min(width - 1 - x);
+                        let new = 2 * distance < col_offset;
+                        pixel(if new { &self.to } else { from }, x, y)
+                    }
+                    TransitionKind::Fade(_) => unreachable!(),
+                };
+                set_pixel(&mut frame.data, x, y, on);
+            }
+        }
+    }
+}
+
+impl Animation for Transition {
+    fn next_frame(&mut self, frame: &mut Frame) -> bool {
+        if self.step >= self.duration {
+            return false;
+        }
+        let from = self.from.get_or_insert_with(|| frame.data.clone()).clone();
+        self.step += 1;
+        self.render(&from, frame);
+        true
+    }
+}
+
+/// Scrambles the bits of `value`. Used as cheap pseudo random number generator that also
+/// works in `no_std` environments.
+const fn scramble(mut value: u32) -> u32 {
+    value ^= value >> 16;
+    value = value.wrapping_mul(0x7feb_352d);
+    value ^= value >> 15;
+    value = value.wrapping_mul(0x846c_a68b);
+    value ^= value >> 16;
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(mut transition: Transition, from: Vec<SingleDisplayData>) -> Vec<Frame> {
+        let mut frame = Frame::new(from.len());
+        frame.data = from;
+        let mut frames = Vec::new();
+        while transition.next_frame(&mut frame) {
+            frames.push(frame.clone());
+        }
+        frames
+    }
+
+    #[test]
+    fn test_all_transitions_end_with_new_content() {
+        let kinds = [
+            TransitionKind::Push(Direction::Left),
+            TransitionKind::Push(Direction::Right),
+            TransitionKind::Push(Direction::Up),
+            TransitionKind::Push(Direction::Down),
+            TransitionKind::Wipe(Direction::Left),
+            TransitionKind::Wipe(Direction::Right),
+            TransitionKind::Wipe(Direction::Up),
+            TransitionKind::Wipe(Direction::Down),
+            TransitionKind::Dissolve(42),
+            TransitionKind::CurtainOpen,
+            TransitionKind::CurtainClose,
+            TransitionKind::Fade(0x0F),
+        ];
+        let from = vec![[0b1010_1010; 8], [0b0101_0101; 8]];
+        let to = vec![[0b1111_0000; 8], [0b0000_1111; 8]];
+        for kind in kinds {
+            let frames = play(Transition::new(kind, 5, to.clone()), from.clone());
+            assert_eq!(frames.len(), 5, "{:?}", kind);
+            assert_eq!(frames[4].data, to, "{:?}", kind);
+        }
+    }
+
+    #[test]
+    fn test_push_left() {
+        let from = vec![[0b1111_1111; 8]];
+        let frames = play(
+            Transition::new(TransitionKind::Push(Direction::Left), 4, vec![[0; 8]]),
+            from,
+        );
+        assert_eq!(frames[0].data[0][0], 0b1111_1100);
+        assert_eq!(frames[1].data[0][0], 0b1111_0000);
+        assert_eq!(frames[2].data[0][0], 0b1100_0000);
+    }
+
+    #[test]
+    fn test_fade_uses_intensity() {
+        let frames = play(
+            Transition::new(TransitionKind::Fade(8), 4, vec![[0xFF; 8]]),
+            vec![[0; 8]],
+        );
+        let intensities: Vec<_> = frames.iter().map(|f| f.intensity[0].unwrap()).collect();
+        assert_eq!(intensities, [3, 0, 3, 8]);
+        assert_eq!(frames[1].data, [[0; 8]]);
+        assert_eq!(frames[2].data, [[0xFF; 8]]);
+    }
+}
@@ -0,0 +1,165 @@
+//! Typewriter effect: reveals a text one character at a time, optionally with a blinking cursor.
+
+use crate::animation::{pixel, set_pixel, Animation, Frame};
+use crate::encoding::encode_string;
+use crate::mappings::SingleDisplayData;
+use crate::{remove_gaps_in_display_text, LED_SQUARE_MATRIX_DIM};
+use alloc::vec::Vec;
+
+/// Reveals a text one character at a time, holds it and clears the display afterwards.
+/// The characters are encoded with [`encode_string`]. If a gap width is set, the layout of
+/// [`remove_gaps_in_display_text`] is used.
+///
+/// The text is not scrolled. Characters that don't fit on the display chain are not visible.
+#[derive(Debug, Clone)]
+pub struct Typewriter {
+    glyphs: Vec<SingleDisplayData>,
+    gap_width: Option<usize>,
+    char_delay: usize,
+    hold: usize,
+    cursor_period: Option<usize>,
+    clear: bool,
+    step: usize,
+    /// Layout of the first `layout_chars` characters.
+    layout: Vec<SingleDisplayData>,
+    layout_chars: usize,
+}
+
+impl Typewriter {
+    /// Creates a new typewriter effect. By default, each character is shown for one frame before
+    /// the next character appears, there is no hold time, no cursor and the display is cleared
+    /// at the end.
+    ///
+    /// * `text` - the text to display
+    /// * `gap_width` - (optional) set's the maximum width/count of empty cols between characters.
+    ///                 Downside is that spaces will be removed.
+    pub fn new(text: &str, gap_width: Option<usize>) -> Self {
+        Self {
+            glyphs: encode_string(text),
+            gap_width,
+            char_delay: 1,
+            hold: 0,
+            cursor_period: None,
+            clear: true,
+            step: 0,
+            layout: Vec::new(),
+            layout_chars: 0,
+        }
+    }
+
+    /// Frames until the next character appears.
+    pub const fn char_delay(mut self, frames: usize) -> Self {
+        self.char_delay = frames;
+        self
+    }
+
+    /// Frames the whole text stays on the display after the last character appeared.
+    pub const fn hold(mut self, frames: usize) -> Self {
+        self.hold = frames;
+        self
+    }
+
+    /// Shows a blinking cursor column after the last character.
+    ///
+    /// * `period` - frames of one on/off cycle of the cursor. The cursor is on in the first half.
+    pub const fn cursor(mut self, period: usize) -> Self {
+        self.cursor_period = Some(period);
+        self
+    }
+
+    /// Whether the display is cleared at the end. Enabled by default.
+    pub const fn clear(mut self, clear: bool) -> Self {
+        self.clear = clear;
+        self
+    }
+
+    /// Updates the cached layout to contain the first `chars` characters.
+    fn update_layout(&mut self, chars: usize) {
+        if self.layout_chars == chars {
+            return;
+        }
+        let glyphs = &self.glyphs[..chars];
+        self.layout = match self.gap_width {
+            Some(gap_width) if chars > 0 => remove_gaps_in_display_text(glyphs, gap_width),
+            _ => glyphs.to_vec(),
+        };
+        self.layout_chars = chars;
+    }
+
+    /// Col of the cursor: one empty col after the last lit col of the layout.
+    fn cursor_col(&self) -> usize {
+        let width = self.layout.len() * LED_SQUARE_MATRIX_DIM;
+        (0..width)
+            .rev()
+            .find(|&x| (0..LED_SQUARE_MATRIX_DIM).any(|y| pixel(&self.layout, x, y)))
+            .map_or(0, |x| x + 2)
+    }
+}
+
+impl Animation for Typewriter {
+    fn next_frame(&mut self, frame: &mut Frame) -> bool {
+        let char_delay = self.char_delay.max(1);
+        let typing = self.glyphs.len() * char_delay;
+        let step = self.step;
+
+        if step >= typing + self.hold + usize::from(self.clear) {
+            return false;
+        }
+        self.step += 1;
+
+        if step >= typing + self.hold {
+            // clear the display at the end
+            frame.set_data(&[]);
+            return true;
+        }
+
+        let chars = (step / char_delay + 1).min(self.glyphs.len());
+        self.update_layout(chars);
+        frame.set_data(&self.layout);
+
+        if let Some(period) = self.cursor_period {
+            let cursor_on = period > 0 && step % period < (period + 1) / 2;
+            let x = self.cursor_col();
+            if cursor_on && x < frame.width() {
+                for y in 0..LED_SQUARE_MATRIX_DIM {
+                    set_pixel(&mut frame.data, x, y, true);
+                }
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::encode_char;
+
+    #[test]
+    fn test_typewriter_reveals_chars() {
+        let mut typewriter = Typewriter::new("HI", None).char_delay(2).hold(1);
+        let mut frame = Frame::new(2);
+        let mut frames = Vec::new();
+        while typewriter.next_frame(&mut frame) {
+            frames.push(frame.data.clone());
+        }
+        let h = encode_char('H');
+        let i = encode_char('I');
+        assert_eq!(frames.len(), 6);
+        assert_eq!(frames[0], [h, [0; 8]]);
+        assert_eq!(frames[1], [h, [0; 8]]);
+        assert_eq!(frames[2], [h, i]);
+        assert_eq!(frames[4], [h, i]);
+        assert_eq!(frames[5], [[0; 8], [0; 8]]);
+    }
+
+    #[test]
+    fn test_typewriter_cursor() {
+        let mut typewriter = Typewriter::new("I", None).cursor(2).clear(false);
+        let mut frame = Frame::new(2);
+        assert!(typewriter.next_frame(&mut frame));
+        // "I" is lit in col 3, so the cursor is in col 5
+        assert_eq!(frame.data[0], [0b0001_0100; 8]);
+        assert!(!typewriter.next_frame(&mut frame));
+    }
+}